@@ -0,0 +1,285 @@
+//! Measures gas burned by actions & the state size at several player counts.
+//!
+//! Game rounds are measured with [`Pricing::Fixed`], and also with
+//! [`Pricing::Exponential`] at the maximum player count, where the price of a
+//! late entry is the most expensive to calculate.
+//!
+//! Results are written as JSON to `target/benchmarks.json` or a path from the
+//! `GOC_BENCH_OUTPUT` environment variable, and the run fails if any of them
+//! exceeds budgets for the same player count & pricing from
+//! `benches/budgets.json`, or if a recorded player count & pricing has no
+//! budget for a measured operation. Player counts can be set with a
+//! comma-separated list in `GOC_BENCH_PLAYERS`, and ones without budgets are
+//! only measured.
+//!
+//! If `GOC_UPDATE_BUDGETS` is set, budgets are replaced with measurements
+//! increased by the margin from `benches/budgets.json` instead.
+
+use game_of_chance_io::*;
+use gstd::ActorId;
+use gtest::{Program, RunResult, System, EXISTENTIAL_DEPOSIT};
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use std::{env, fs, iter, process::ExitCode};
+
+const ADMIN: u64 = 100;
+const FIRST_PLAYER: u64 = 1_000_000;
+const PARTICIPATION_COST: u128 = EXISTENTIAL_DEPOSIT;
+const DURATION: u64 = 1000;
+const BUDGETS_PATH: &str = "benches/budgets.json";
+const DEFAULT_OUTPUT_PATH: &str = "target/benchmarks.json";
+// The last player is entered by the measured `Action::Enter`.
+const DEFAULT_PLAYER_COUNTS: [usize; 5] = [0, 100, 1_000, 10_000, MAX_NUMBER_OF_PLAYERS - 1];
+// The smallest rate keeps prices of all entries mintable.
+const EXPONENTIAL_PRICING: Pricing = Pricing::Exponential { rate: 1 };
+
+#[derive(Serialize)]
+struct Measurement {
+    /// The number of players before the measured `Action::Enter`.
+    players: usize,
+    pricing: &'static str,
+    start: u64,
+    enter: u64,
+    pick_winner: u64,
+    /// The size of the encoded `State` with all players.
+    state_size: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Budgets {
+    /// A margin in percent added to measurements when budgets are updated.
+    margin_percent: u64,
+    budgets: Vec<Budget>,
+}
+
+/// Budgets for a player count & pricing. [`None`] means that a budget isn't
+/// recorded, e.g. for a newly measured operation.
+#[derive(Serialize, Deserialize)]
+struct Budget {
+    players: usize,
+    pricing: String,
+    #[serde(default)]
+    start: Option<u64>,
+    #[serde(default)]
+    enter: Option<u64>,
+    #[serde(default)]
+    pick_winner: Option<u64>,
+    #[serde(default)]
+    state_size: Option<u64>,
+}
+
+impl Budget {
+    fn new(measurement: &Measurement, margin_percent: u64) -> Self {
+        let with_margin = |value: u64| {
+            Some(
+                value
+                    .saturating_mul(100 + margin_percent)
+                    .saturating_add(99)
+                    / 100,
+            )
+        };
+
+        Self {
+            players: measurement.players,
+            pricing: measurement.pricing.into(),
+            start: with_margin(measurement.start),
+            enter: with_margin(measurement.enter),
+            pick_winner: with_margin(measurement.pick_winner),
+            state_size: with_margin(measurement.state_size),
+        }
+    }
+}
+
+#[track_caller]
+fn send(program: &Program, from: u64, action: Action, value: u128) -> RunResult {
+    let result = program.send_with_value(from, action, value);
+    let from = ActorId::from(from);
+    let reply = result
+        .log()
+        .iter()
+        .find(|log| log.destination().as_ref() == from.as_ref())
+        .map(|log| Result::<Event, Error>::decode(&mut log.payload()));
+
+    assert!(
+        matches!(reply, Some(Ok(Ok(_)))),
+        "{action:?} failed: {reply:?}"
+    );
+
+    result
+}
+
+fn pricing_name(pricing: Pricing) -> &'static str {
+    match pricing {
+        Pricing::Fixed => "fixed",
+        Pricing::Linear { .. } => "linear",
+        Pricing::Exponential { .. } => "exponential",
+        Pricing::EarlyBird { .. } => "early_bird",
+        Pricing::DutchAuction { .. } => "dutch_auction",
+    }
+}
+
+fn measure(players: usize, pricing: Pricing) -> Measurement {
+    let system = System::new();
+    let program = Program::current(&system);
+
+    assert!(!program
+        .send(
+            ADMIN,
+            Initialize {
+                admin: ADMIN.into(),
+                existential_deposit: EXISTENTIAL_DEPOSIT,
+            },
+        )
+        .main_failed());
+
+    let start = Action::Start {
+        duration: DURATION,
+        participation_cost: PARTICIPATION_COST,
+        fungible_token: None,
+        selection: Selection::Uniform,
+        raffle: None,
+        pricing,
+    };
+
+    send(&program, ADMIN, start, 0);
+
+    let enter = |entries| {
+        let player = FIRST_PLAYER + entries as u64;
+        // Entries are sent within the first block, so no time elapses.
+        let price = pricing.price(PARTICIPATION_COST, DURATION, entries as u32, 0);
+
+        system.mint_to(player, price);
+        send(&program, player, Action::Enter(None), price)
+    };
+
+    for entries in 0..players {
+        enter(entries);
+    }
+
+    let enter = enter(players).main_gas_burned();
+    let state_size = program.read_state::<State>().unwrap().encoded_size() as u64;
+
+    system.spend_blocks((DURATION / 1000) as _);
+
+    let pick_winner = send(&program, ADMIN, Action::PickWinner(None), 0).main_gas_burned();
+    // Starting a new round clears all players of the previous one.
+    let start = send(&program, ADMIN, start, 0).main_gas_burned();
+
+    Measurement {
+        players,
+        pricing: pricing_name(pricing),
+        start: start.0,
+        enter: enter.0,
+        pick_winner: pick_winner.0,
+        state_size,
+    }
+}
+
+fn main() -> ExitCode {
+    let player_counts = env::var("GOC_BENCH_PLAYERS").map_or_else(
+        |_| DEFAULT_PLAYER_COUNTS.to_vec(),
+        |counts| {
+            counts
+                .split(',')
+                .map(|count| count.trim().parse().expect("invalid player count"))
+                .collect()
+        },
+    );
+    let mut budgets: Budgets =
+        serde_json::from_str(&fs::read_to_string(BUDGETS_PATH).expect("failed to read budgets"))
+            .expect("failed to parse budgets");
+    let output_path = env::var("GOC_BENCH_OUTPUT").unwrap_or_else(|_| DEFAULT_OUTPUT_PATH.into());
+    let update_budgets = env::var_os("GOC_UPDATE_BUDGETS").is_some();
+
+    let mut measurements = vec![];
+    let mut exceeded = vec![];
+
+    let cases = player_counts.into_iter().flat_map(|players| {
+        let exponential =
+            (players == MAX_NUMBER_OF_PLAYERS - 1).then_some((players, EXPONENTIAL_PRICING));
+
+        iter::once((players, Pricing::Fixed)).chain(exponential)
+    });
+
+    for (players, pricing) in cases {
+        let measurement = measure(players, pricing);
+        let pricing = measurement.pricing;
+
+        println!(
+            "{players} players, {pricing} pricing: start {}, enter {}, pick_winner {} gas, state {} bytes",
+            measurement.start, measurement.enter, measurement.pick_winner, measurement.state_size
+        );
+
+        if !update_budgets {
+            match budgets
+                .budgets
+                .iter()
+                .find(|budget| budget.players == players && budget.pricing == pricing)
+            {
+                Some(budget) => {
+                    for (name, value, budget) in [
+                        ("start", measurement.start, budget.start),
+                        ("enter", measurement.enter, budget.enter),
+                        ("pick_winner", measurement.pick_winner, budget.pick_winner),
+                        ("state_size", measurement.state_size, budget.state_size),
+                    ] {
+                        match budget {
+                            Some(budget) if value <= budget => {}
+                            Some(budget) => exceeded.push(format!(
+                                "{name} with {players} players & {pricing} pricing: {value} > {budget}"
+                            )),
+                            None => exceeded.push(format!(
+                                "{name} with {players} players & {pricing} pricing: no budget"
+                            )),
+                        }
+                    }
+                }
+                None => println!("There are no budgets for {players} players & {pricing} pricing."),
+            }
+        }
+
+        measurements.push(measurement);
+    }
+
+    if update_budgets {
+        for measurement in &measurements {
+            let budget = Budget::new(measurement, budgets.margin_percent);
+
+            match budgets.budgets.iter_mut().find(|budget| {
+                budget.players == measurement.players && budget.pricing == measurement.pricing
+            }) {
+                Some(old_budget) => *old_budget = budget,
+                None => budgets.budgets.push(budget),
+            }
+        }
+
+        budgets
+            .budgets
+            .sort_by(|a, b| (a.players, &a.pricing).cmp(&(b.players, &b.pricing)));
+        fs::write(
+            BUDGETS_PATH,
+            serde_json::to_string_pretty(&budgets).expect("failed to serialize budgets") + "\n",
+        )
+        .expect("failed to write budgets");
+        println!("Budgets are written to `{BUDGETS_PATH}`.");
+    }
+
+    fs::write(
+        &output_path,
+        serde_json::to_string_pretty(&measurements).expect("failed to serialize measurements"),
+    )
+    .expect("failed to write measurements");
+    println!("Measurements are written to `{output_path}`.");
+
+    if exceeded.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        eprintln!("Budgets are exceeded or missing:");
+
+        for exceeded in exceeded {
+            eprintln!("- {exceeded}");
+        }
+
+        ExitCode::FAILURE
+    }
+}
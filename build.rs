@@ -1,8 +1,14 @@
 use game_of_chance_io::ContractMetadata;
 use gear_wasm_builder::WasmBuilder;
 use gmeta::Metadata;
+use std::env;
 
 fn main() {
+    // Test hooks must never get into a production binary.
+    if env::var_os("CARGO_FEATURE_TESTING").is_some() && env::var("PROFILE").unwrap() == "release" {
+        panic!("the `testing` feature can't be enabled in release builds");
+    }
+
     WasmBuilder::with_meta(ContractMetadata::repr())
         .exclude_features(["binary-vendor"])
         .build();
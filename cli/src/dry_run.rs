@@ -0,0 +1,143 @@
+use super::*;
+use game_of_chance_client::{
+    gtest::GtestTransport,
+    record::{Message, Record, Recorder},
+};
+use gtest::RunResult;
+use std::fs;
+
+/// The default sender of script lines.
+const DEFAULT_SENDER: &str = "3";
+
+/// A line of a dry run script.
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct Line {
+    /// The sender.
+    #[arg(long, value_parser = parse_actor_id, default_value = DEFAULT_SENDER)]
+    from: ActorId,
+    #[command(subcommand)]
+    command: LineCommand,
+}
+
+#[derive(Subcommand)]
+enum LineCommand {
+    #[command(flatten)]
+    Deploy(DeployCommand),
+    #[command(flatten)]
+    Round(RoundCommand),
+    /// Mints the native value to an actor.
+    Mint {
+        #[arg(value_parser = parse_actor_id)]
+        to: ActorId,
+        amount: u128,
+    },
+    /// Spends blocks.
+    Wait { blocks: u32 },
+}
+
+pub async fn run(script: Option<PathBuf>, log: Box<dyn Write>) -> CliResult {
+    let script = match script {
+        Some(path) => fs::read_to_string(path)?,
+        None => io::read_to_string(io::stdin())?,
+    };
+    let system = System::new();
+    let mut log = Some(log);
+    let mut client = None;
+    let mut is_watching = false;
+
+    for (number, line) in script.lines().enumerate() {
+        let number = number + 1;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        println!("> {line}");
+
+        let Line { from, command } = Line::try_parse_from(line.split_whitespace())
+            .map_err(|error| format!("line {number}: {error}"))?;
+
+        match command {
+            LineCommand::Deploy(DeployCommand::Deploy {
+                admin,
+                existential_deposit,
+            }) => {
+                if client.is_some() {
+                    return Err(format!("line {number}: the contract is already deployed").into());
+                }
+
+                let initialize = Initialize {
+                    admin: admin.unwrap_or(from),
+                    existential_deposit,
+                };
+                let (program, reply) = deploy_to_gtest(&system, from, 0, initialize)?;
+                let mut recorder = Recorder::new(
+                    GtestTransport::new(&system, program, from),
+                    log.take().unwrap(),
+                );
+
+                recorder.write(&Record {
+                    timestamp: system.block_timestamp(),
+                    source: from,
+                    value: 0,
+                    message: Message::Init(initialize, reply.clone()),
+                })?;
+
+                if let Err(error) = reply {
+                    return Err(format!(
+                        "line {number}: failed to initialize the contract: {error:?}"
+                    )
+                    .into());
+                }
+
+                println!("{GTEST_PROGRAM_ID}");
+
+                client = Some(Client::new(recorder));
+            }
+            LineCommand::Round(RoundCommand::Watch) => is_watching = true,
+            LineCommand::Round(command) => {
+                let client = client
+                    .as_mut()
+                    .ok_or_else(|| format!("line {number}: the contract isn't deployed"))?;
+                let is_action = !matches!(command, RoundCommand::Status);
+
+                client.transport_mut().transport_mut().set_sender(from);
+
+                match execute(client, command).await {
+                    Ok(()) => {}
+                    // Contract errors are expected in rehearsals.
+                    Err(ClientError::Contract(error)) => println!("{error:#?}"),
+                    Err(error) => return Err(format!("line {number}: {error}").into()),
+                }
+
+                if is_watching && is_action {
+                    if let Some(result) = client.transport().transport().last_result() {
+                        print_notifications(result);
+                    }
+                }
+            }
+            LineCommand::Mint { to, amount } => system.mint_to(<[u8; 32]>::from(to), amount),
+            LineCommand::Wait { blocks } => {
+                system.spend_blocks(blocks);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_notifications(result: &RunResult) {
+    for log in result.log() {
+        // Notifications are sent to the user-visible event stream, i.e. to the
+        // zero address.
+        if log.destination().as_ref() != [0; 32] {
+            continue;
+        }
+
+        if let Ok(notification) = Notification::decode(&mut log.payload()) {
+            println!("{notification:#?}");
+        }
+    }
+}
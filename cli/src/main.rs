@@ -0,0 +1,335 @@
+//! An admin CLI for the Game of chance contract.
+//!
+//! Every subcommand is sent to a Gear node through `gclient`, except
+//! `dry-run`, which executes a script of the same subcommands against an
+//! in-process `gtest` system, so no node is needed for rehearsals, and
+//! `replay`, which replays a log recorded with `--record` against `gtest`.
+
+use clap::{Args, Parser, Subcommand};
+use game_of_chance::WASM_BINARY_OPT;
+use game_of_chance_client::{Client, ClientError, Transport};
+use game_of_chance_io::*;
+use gstd::ActorId;
+use gtest::{Program, System};
+use parity_scale_codec::Decode;
+use std::{
+    error::Error as StdError,
+    fmt::Display,
+    fs::OpenOptions,
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+mod dry_run;
+mod node;
+mod replay;
+
+type CliResult<T = ()> = Result<T, Box<dyn StdError>>;
+
+/// The ID of the contract deployed in `gtest`.
+const GTEST_PROGRAM_ID: u64 = 1;
+
+#[derive(Parser)]
+#[command(version, about = "Manages the Game of chance contract")]
+struct Cli {
+    #[command(flatten)]
+    node: NodeArgs,
+    /// A path to a log to append records of sent messages to.
+    ///
+    /// See the `record` module of `game-of-chance-client` for the format.
+    #[arg(long, global = true)]
+    record: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args)]
+struct NodeArgs {
+    /// A node address.
+    #[arg(long, global = true, default_value = "ws://127.0.0.1")]
+    address: String,
+    /// A node port.
+    #[arg(long, global = true, default_value_t = 9944)]
+    port: u16,
+    /// A secret URI of an account to send messages from.
+    #[arg(long, global = true, default_value = "//Alice")]
+    suri: String,
+    /// The contract address, required for all subcommands except `deploy`.
+    #[arg(long, global = true, value_parser = parse_actor_id)]
+    program: Option<ActorId>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(flatten)]
+    Deploy(DeployCommand),
+    #[command(flatten)]
+    Round(RoundCommand),
+    /// Executes a script of subcommands against an in-process `gtest` system.
+    ///
+    /// Each line of the script is a subcommand without node options, e.g.
+    /// `--from 3 start --duration 5000 --participation-cost 10000`. Empty
+    /// lines & lines starting with `#` are skipped.
+    DryRun {
+        /// A path to the script. Standard input is read if it's omitted.
+        script: Option<PathBuf>,
+    },
+    /// Replays a recorded log against an in-process `gtest` system.
+    ///
+    /// Stops on the first reply that diverges from the recorded one. Game
+    /// rounds in fungible tokens can't be replayed.
+    Replay {
+        /// A path to the log.
+        log: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum DeployCommand {
+    /// Uploads & initializes the contract.
+    #[command(alias = "init")]
+    Deploy {
+        /// The contract admin. The sender is used if it's omitted.
+        #[arg(long, value_parser = parse_actor_id)]
+        admin: Option<ActorId>,
+        /// The existential deposit of the network.
+        #[arg(long, default_value_t = 0)]
+        existential_deposit: u128,
+    },
+}
+
+#[derive(Subcommand)]
+enum RoundCommand {
+    /// Starts a game round.
+    Start {
+        /// The entry stage duration in milliseconds.
+        #[arg(long)]
+        duration: u64,
+        #[arg(long)]
+        participation_cost: u128,
+        /// An FT contract address. The native value is used if it's omitted.
+        #[arg(long, value_parser = parse_actor_id)]
+        fungible_token: Option<ActorId>,
+        /// A strategy of picking winners: `uniform`, `weighted`, or
+        /// `multiple:<number of winners>`.
+        #[arg(long, default_value = "uniform", value_parser = parse_selection)]
+        selection: Selection,
+        /// The number of tickets for sale. The number of players is unlimited
+        /// if it's omitted.
+        #[arg(long)]
+        tickets: Option<u32>,
+        /// What happens to unsold tickets at the end of the entry stage:
+        /// `draw` or `refund`.
+        #[arg(long, default_value = "draw", value_parser = parse_unsold_tickets)]
+        unsold: UnsoldTickets,
+        /// A pricing of entries: `fixed`, `linear:<step>`,
+        /// `exponential:<rate in basis points>`, `early-bird:<price>:<duration>`,
+        /// or `dutch-auction:<floor>`.
+        #[arg(long, default_value = "fixed", value_parser = parse_pricing)]
+        pricing: Pricing,
+    },
+    /// Enters the current game round.
+    Enter {
+        /// An expected game round.
+        #[arg(long)]
+        round: Option<u64>,
+        /// The attached native value.
+        #[arg(long, default_value_t = 0)]
+        value: u128,
+    },
+    /// Picks a winner of the current game round.
+    PickWinner {
+        /// An expected game round.
+        #[arg(long)]
+        round: Option<u64>,
+    },
+    /// Prints the price of entering the current game round now.
+    Quote,
+    /// Prints the contract state.
+    Status,
+    /// Prints notifications from the contract as they arrive.
+    Watch,
+}
+
+/// Parses an actor ID from a 0x-prefixed hex string of 32 bytes or from a
+/// decimal number as `gtest` does.
+fn parse_actor_id(string: &str) -> Result<ActorId, String> {
+    if let Some(hex) = string.strip_prefix("0x") {
+        let mut bytes = [0; 32];
+
+        hex::decode_to_slice(hex, &mut bytes)
+            .map_err(|error| format!("invalid actor ID: {error}"))?;
+
+        Ok(bytes.into())
+    } else {
+        string
+            .parse::<u64>()
+            .map(Into::into)
+            .map_err(|error| format!("invalid actor ID: {error}"))
+    }
+}
+
+/// Parses a [`Selection`] from `uniform`, `weighted`, or
+/// `multiple:<number of winners>`.
+fn parse_selection(string: &str) -> Result<Selection, String> {
+    match string {
+        "uniform" => Ok(Selection::Uniform),
+        "weighted" => Ok(Selection::Weighted),
+        _ => string
+            .strip_prefix("multiple:")
+            .ok_or_else(|| format!("unknown selection `{string}`"))?
+            .parse()
+            .map(Selection::Multiple)
+            .map_err(|error| format!("invalid number of winners: {error}")),
+    }
+}
+
+/// Parses [`UnsoldTickets`] from `draw` or `refund`.
+fn parse_unsold_tickets(string: &str) -> Result<UnsoldTickets, String> {
+    match string {
+        "draw" => Ok(UnsoldTickets::Draw),
+        "refund" => Ok(UnsoldTickets::Refund),
+        _ => Err(format!("unknown policy for unsold tickets `{string}`")),
+    }
+}
+
+/// Parses a [`Pricing`] from `fixed`, `linear:<step>`,
+/// `exponential:<rate>`, `early-bird:<price>:<duration>`, or
+/// `dutch-auction:<floor>`.
+fn parse_pricing(string: &str) -> Result<Pricing, String> {
+    fn number<T: FromStr>(string: Option<&str>, name: &str) -> Result<T, String>
+    where
+        T::Err: Display,
+    {
+        string
+            .ok_or_else(|| format!("missing {name}"))?
+            .parse()
+            .map_err(|error| format!("invalid {name}: {error}"))
+    }
+
+    let mut parts = string.split(':');
+    let pricing = match parts.next() {
+        Some("fixed") => Pricing::Fixed,
+        Some("linear") => Pricing::Linear {
+            step: number(parts.next(), "step")?,
+        },
+        Some("exponential") => Pricing::Exponential {
+            rate: number(parts.next(), "rate")?,
+        },
+        Some("early-bird") => Pricing::EarlyBird {
+            price: number(parts.next(), "early-bird price")?,
+            duration: number(parts.next(), "early-bird duration")?,
+        },
+        Some("dutch-auction") => Pricing::DutchAuction {
+            floor: number(parts.next(), "floor")?,
+        },
+        _ => return Err(format!("unknown pricing `{string}`")),
+    };
+
+    if parts.next().is_some() {
+        return Err(format!("too many parameters of pricing `{string}`"));
+    }
+
+    Ok(pricing)
+}
+
+/// Opens a log for [`Recorder`](game_of_chance_client::record::Recorder),
+/// or discards records if `path` is `None`.
+fn open_log(path: Option<PathBuf>) -> io::Result<Box<dyn Write>> {
+    Ok(match path {
+        Some(path) => Box::new(OpenOptions::new().create(true).append(true).open(path)?),
+        None => Box::new(io::sink()),
+    })
+}
+
+/// Uploads & initializes the contract in `gtest`, returning its reply.
+fn deploy_to_gtest<'a>(
+    system: &'a System,
+    source: ActorId,
+    value: u128,
+    initialize: Initialize,
+) -> CliResult<(Program<'a>, Result<(), Error>)> {
+    let program = Program::from_opt_and_meta_code_with_id(
+        system,
+        GTEST_PROGRAM_ID,
+        WASM_BINARY_OPT.into(),
+        None,
+    );
+    let source = <[u8; 32]>::from(source);
+    let result = program.send_with_value(source, initialize, value);
+    let reply = result
+        .log()
+        .iter()
+        .find(|log| log.destination().as_ref() == source)
+        .ok_or("the contract didn't reply to the initialization")?;
+
+    Ok((program, Decode::decode(&mut reply.payload())?))
+}
+
+/// Executes all [`RoundCommand`]s except [`RoundCommand::Watch`], which
+/// depends on a transport.
+async fn execute<T: Transport>(
+    client: &mut Client<T>,
+    command: RoundCommand,
+) -> Result<(), ClientError<T::Error>> {
+    let event = match command {
+        RoundCommand::Start {
+            duration,
+            participation_cost,
+            fungible_token,
+            selection,
+            tickets,
+            unsold,
+            pricing,
+        } => {
+            let raffle = tickets.map(|tickets| Raffle { tickets, unsold });
+
+            client
+                .start(
+                    duration,
+                    participation_cost,
+                    fungible_token,
+                    selection,
+                    raffle,
+                    pricing,
+                )
+                .await?
+        }
+        RoundCommand::Enter { round, value } => client.enter(round, value).await?,
+        RoundCommand::PickWinner { round } => client.pick_winner(round).await?,
+        RoundCommand::Quote => {
+            let State { round, price, .. } = client.state().await?;
+
+            println!("Round {round}: {price}");
+
+            return Ok(());
+        }
+        RoundCommand::Status => {
+            println!("{:#?}", client.state().await?);
+
+            return Ok(());
+        }
+        RoundCommand::Watch => unreachable!("`watch` must be handled by a transport"),
+    };
+
+    println!("{event:#?}");
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> CliResult {
+    let Cli {
+        node,
+        record,
+        command,
+    } = Cli::parse();
+    let log = open_log(record)?;
+
+    match command {
+        Command::DryRun { script } => dry_run::run(script, log).await,
+        Command::Replay { log } => replay::run(log).await,
+        command => node::run(node, command, log).await,
+    }
+}
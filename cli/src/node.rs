@@ -0,0 +1,126 @@
+use super::*;
+use game_of_chance::WASM_BINARY_OPT;
+use game_of_chance_client::{
+    gclient::{self as transport, GclientTransport, NodeError},
+    record::{Message, Record, Recorder},
+};
+use gclient::{Event as NodeEvent, EventProcessor, GearApi, GearEvent, WSAddress};
+use parity_scale_codec::{Decode, Encode};
+
+pub async fn run(args: NodeArgs, command: Command, mut log: Box<dyn Write>) -> CliResult {
+    let api = GearApi::init_with(WSAddress::new(args.address, args.port), args.suri).await?;
+
+    let command = match command {
+        Command::Deploy(DeployCommand::Deploy {
+            admin,
+            existential_deposit,
+        }) => {
+            let admin = admin.unwrap_or_else(|| <[u8; 32]>::from(api.account_id().clone()).into());
+
+            return deploy(
+                &api,
+                Initialize {
+                    admin,
+                    existential_deposit,
+                },
+                &mut log,
+            )
+            .await;
+        }
+        Command::Round(command) => command,
+        Command::DryRun { .. } | Command::Replay { .. } => {
+            unreachable!("`dry-run` & `replay` don't need a node")
+        }
+    };
+
+    let program = args
+        .program
+        .ok_or("the contract address must be set with `--program`")?;
+
+    if let RoundCommand::Watch = command {
+        return watch(&api, program).await;
+    }
+
+    let mut client = Client::new(Recorder::new(
+        GclientTransport::new(api, program).await?,
+        log,
+    ));
+
+    Ok(execute(&mut client, command).await?)
+}
+
+async fn deploy(api: &GearApi, initialize: Initialize, log: &mut dyn Write) -> CliResult {
+    let mut listener = api.subscribe().await?;
+    let payload = initialize.encode();
+    let gas_limit = api
+        .calculate_upload_gas(None, WASM_BINARY_OPT.into(), payload.clone(), 0, true)
+        .await?
+        .min_limit;
+    let (message_id, program_id, _) = api
+        .upload_program_bytes(
+            WASM_BINARY_OPT,
+            gclient::now_micros().to_le_bytes(),
+            payload,
+            gas_limit,
+            0,
+        )
+        .await?;
+    let (_, raw_reply, _) = listener.reply_bytes_on(message_id).await?;
+    let timestamp = transport::reply_timestamp(api, message_id).await?;
+    let reply = Result::<(), Error>::decode(&mut raw_reply?.as_slice())?;
+
+    // The contract is already initialized, so the record is written even if
+    // the timestamp is unavailable, and only then the error is returned.
+    let record_timestamp = if let Some(timestamp) = timestamp {
+        timestamp
+    } else {
+        writeln!(
+            log,
+            "# {}, so the next record has the timestamp of a later block",
+            NodeError::TimestampUnavailable
+        )?;
+
+        api.last_block_timestamp().await?
+    };
+    let record = Record {
+        timestamp: record_timestamp,
+        source: <[u8; 32]>::from(api.account_id().clone()).into(),
+        value: 0,
+        message: Message::Init(initialize, reply.clone()),
+    };
+
+    writeln!(log, "{}", record.to_line())?;
+
+    if let Err(error) = reply {
+        return Err(format!("failed to initialize the contract: {error:?}").into());
+    }
+
+    println!("0x{}", hex::encode(program_id));
+    timestamp.ok_or(NodeError::TimestampUnavailable)?;
+
+    Ok(())
+}
+
+async fn watch(api: &GearApi, program: ActorId) -> CliResult {
+    let mut listener = api.subscribe().await?;
+    let program = <[u8; 32]>::from(program);
+
+    loop {
+        let NodeEvent::Gear(GearEvent::UserMessageSent { message, .. }) =
+            listener.next_event().await?
+        else {
+            continue;
+        };
+
+        // Notifications are sent to the user-visible event stream, i.e. to
+        // the zero address.
+        if message.source.0 != program || message.destination.0 != [0; 32] {
+            continue;
+        }
+
+        match Notification::decode(&mut message.payload.0.as_slice()) {
+            Ok(notification) => println!("{notification:#?}"),
+            Err(error) => eprintln!("failed to decode a notification: {error}"),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use super::*;
+use game_of_chance_client::{
+    gtest::GtestTransport,
+    record::{self, Message, Record},
+};
+use std::{fmt::Debug, fs};
+
+/// The duration of a `gtest` block in milliseconds.
+const BLOCK_DURATION: u64 = 1000;
+
+/// Prints fields of `after` that differ from `before`.
+macro_rules! print_state_diff {
+    ($before:expr, $after:expr, $($field:ident),+ $(,)?) => {{
+        // Destructuring makes sure a new field isn't missed.
+        let State { $($field),+ } = $after;
+
+        $(
+            if $before.$field != *$field {
+                println!(
+                    "  {}: {:?} -> {:?}",
+                    stringify!($field),
+                    $before.$field,
+                    $field,
+                );
+            }
+        )+
+    }};
+}
+
+/// Replays `log` against `gtest`.
+///
+/// `gtest` moves time by whole blocks only, so blocks are spent to keep
+/// intervals between recorded timestamps. If the first recorded timestamp
+/// isn't in the past of `gtest`, the log is replayed with the same
+/// timestamps, otherwise they're shifted, and so are times in recorded
+/// replies before they're compared.
+///
+/// A winner is picked with a seed from a block timestamp, so with the
+/// `testing` feature, the seed is overridden with the recorded timestamp
+/// before each [`Action::PickWinner`]. Without it, a shifted log will most
+/// likely diverge on `Event::Winner` or `Event::Winners`.
+///
+/// Game rounds in fungible tokens aren't replayed, because the recorded FT
+/// contract & its balances aren't available in `gtest`.
+pub async fn run(log: PathBuf) -> CliResult {
+    let log = fs::read_to_string(log)?;
+    let mut records = record::parse(&log);
+
+    let (number, first) = records.next().ok_or("the log is empty")?;
+    let first = first.map_err(|error| format!("line {number}: {error}"))?;
+    let Message::Init(initialize, expected) = first.message else {
+        return Err(format!("line {number}: the log doesn't start with an initialization").into());
+    };
+
+    let system = System::new();
+    let origin = system.block_timestamp().max(first.timestamp);
+    let wait = |timestamp: u64| {
+        let target = origin + timestamp.saturating_sub(first.timestamp);
+        let blocks = target.saturating_sub(system.block_timestamp()) / BLOCK_DURATION;
+
+        if blocks != 0 {
+            system.spend_blocks(blocks as _);
+        }
+    };
+
+    wait(first.timestamp);
+    mint(&system, first.source, first.value);
+    println!("> line {number}: {:?} from {:?}", initialize, first.source);
+
+    let (program, reply) = deploy_to_gtest(&system, first.source, first.value, initialize)?;
+
+    check_reply(number, &expected, &reply)?;
+
+    let shift = |timestamp: u64| i128::from(system.block_timestamp()) - i128::from(timestamp);
+    // The shift of the last started game round's `ending`.
+    let mut ending_shift = shift(first.timestamp);
+    let mut client = Client::new(GtestTransport::new(&system, program, first.source));
+    let mut state = client.state().await?;
+
+    for (number, record) in records {
+        let Record {
+            timestamp,
+            source,
+            value,
+            message,
+        } = record.map_err(|error| format!("line {number}: {error}"))?;
+        let Message::Handle(action, expected) = message else {
+            return Err(format!("line {number}: the contract is already initialized").into());
+        };
+
+        if let Action::Start {
+            fungible_token: Some(fungible_token),
+            ..
+        } = action
+        {
+            return Err(format!(
+                "line {number}: can't replay a game round in fungible tokens of \
+                 {fungible_token:?}, FT contracts aren't deployed to `gtest`"
+            )
+            .into());
+        }
+
+        wait(timestamp);
+        mint(&system, source, value);
+        println!("> line {number}: {action:?} from {source:?} with {value}");
+
+        #[cfg(feature = "testing")]
+        if let Action::PickWinner(_) = action {
+            override_seed(client.transport(), state.admin, timestamp)?;
+        }
+
+        client.transport_mut().set_sender(source);
+
+        let now_shift = shift(timestamp);
+        let reply = client.transport_mut().send(action, value).await?;
+        let next_state = client.state().await?;
+
+        print_state_diff!(
+            state,
+            &next_state,
+            admin,
+            started,
+            ending,
+            players,
+            stakes,
+            prize_fund,
+            participation_cost,
+            winner,
+            winners,
+            selection,
+            raffle,
+            pricing,
+            price,
+            fungible_token,
+            status,
+            reserved_gas,
+            token_reply_deposit,
+            token_reply_timeout,
+            existential_deposit,
+            dust,
+            subscribers,
+            round,
+            player_stats,
+            leaderboards,
+        );
+
+        if let Ok(Event::Started { .. }) = expected {
+            ending_shift = now_shift;
+        }
+
+        check_reply(
+            number,
+            &shift_times(expected, now_shift, ending_shift),
+            &reply,
+        )?;
+
+        state = next_state;
+    }
+
+    println!("The log is replayed without divergences.");
+
+    Ok(())
+}
+
+fn mint(system: &System, actor: ActorId, value: u128) {
+    if value != 0 {
+        system.mint_to(<[u8; 32]>::from(actor), value);
+    }
+}
+
+/// Shifts times in a recorded `reply` by `now_shift` & times of a game round
+/// ending by `ending_shift`.
+fn shift_times(
+    mut reply: Result<Event, Error>,
+    now_shift: i128,
+    ending_shift: i128,
+) -> Result<Event, Error> {
+    let shifted =
+        |time: u64, shift: i128| (i128::from(time) + shift).clamp(0, u64::MAX.into()) as u64;
+
+    match &mut reply {
+        Ok(Event::Started { ending, .. }) => *ending = shifted(*ending, now_shift),
+        Err(Error::RoundIsActive { ending, .. }) => *ending = shifted(*ending, ending_shift),
+        Err(Error::EntryStageNotOver { ending, now } | Error::EntryStageOver { ending, now }) => {
+            *ending = shifted(*ending, ending_shift);
+            *now = shifted(*now, now_shift);
+        }
+        _ => (),
+    }
+
+    reply
+}
+
+/// Makes the contract pick winners with `seed` like it did when the log was
+/// recorded.
+#[cfg(feature = "testing")]
+fn override_seed(transport: &GtestTransport, admin: ActorId, seed: u64) -> CliResult {
+    use game_of_chance_io::testing::{RandomnessOverride, TestAction, TestEvent};
+
+    let admin = <[u8; 32]>::from(admin);
+    let result = transport.program().send(
+        admin,
+        TestAction::OverrideRandomness(Some(RandomnessOverride::Seed(seed))),
+    );
+    let reply = result
+        .log()
+        .iter()
+        .find(|log| log.destination().as_ref() == admin)
+        .ok_or("the contract didn't reply to the randomness override")?;
+
+    match Result::<TestEvent, Error>::decode(&mut reply.payload())? {
+        Ok(_) => Ok(()),
+        Err(error) => Err(format!("failed to override the randomness: {error:?}").into()),
+    }
+}
+
+fn check_reply<T: Debug + PartialEq>(number: usize, expected: &T, reply: &T) -> CliResult {
+    if expected == reply {
+        println!("  {reply:?}");
+
+        Ok(())
+    } else {
+        Err(format!("line {number}: diverged, expected `{expected:?}`, got `{reply:?}`").into())
+    }
+}
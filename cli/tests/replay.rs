@@ -0,0 +1,139 @@
+use game_of_chance_client::record::{Message, Record};
+use game_of_chance_io::*;
+use std::{
+    env, fs,
+    process::{Command, Output},
+};
+
+const ADMIN: u64 = 3;
+const FUNGIBLE_TOKEN: u64 = 200;
+
+/// Replays `log` with the CLI, returning its output.
+fn replay(log: String) -> Output {
+    let path = env::temp_dir().join(format!("goc-replay-{}.log", std::process::id()));
+
+    fs::write(&path, log).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_game-of-chance-cli"))
+        .arg("replay")
+        .arg(&path)
+        .output()
+        .unwrap();
+
+    fs::remove_file(path).unwrap();
+
+    output
+}
+
+#[test]
+fn fungible_token_round() {
+    let record = |message| Record {
+        timestamp: 0,
+        source: ADMIN.into(),
+        value: 0,
+        message,
+    };
+    let log: String = [
+        record(Message::Init(
+            Initialize {
+                admin: ADMIN.into(),
+                existential_deposit: 0,
+            },
+            Ok(()),
+        )),
+        record(Message::Handle(
+            Action::Start {
+                duration: 2000,
+                participation_cost: 10000,
+                fungible_token: Some(FUNGIBLE_TOKEN.into()),
+                selection: Selection::Uniform,
+                raffle: None,
+                pricing: Pricing::Fixed,
+            },
+            Err(Error::TokenTimeout),
+        )),
+    ]
+    .iter()
+    .map(|record| record.to_line() + "\n")
+    .collect();
+    let output = replay(log);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("line 2: can't replay a game round in fungible tokens"),
+        "{stderr}"
+    );
+}
+
+// `gtest` of the replay starts later than the recorded one, so the log is
+// shifted, and the winner is the same only if the recorded seed is reused.
+#[cfg(feature = "testing")]
+#[tokio::test]
+async fn native_round() {
+    use game_of_chance::WASM_BINARY_OPT;
+    use game_of_chance_client::{gtest::GtestTransport, record::Recorder, Client};
+    use gtest::{Program, System, EXISTENTIAL_DEPOSIT};
+
+    const PLAYERS: [u64; 3] = [4, 5, 6];
+    const PARTICIPATION_COST: u128 = 10000;
+    const DURATION: u64 = 2000;
+
+    let system = System::new();
+    let program = Program::from_opt_and_meta_code_with_id(&system, 1, WASM_BINARY_OPT.into(), None);
+    let initialize = Initialize {
+        admin: ADMIN.into(),
+        existential_deposit: EXISTENTIAL_DEPOSIT,
+    };
+    let timestamp = system.block_timestamp();
+
+    assert!(!program.send(ADMIN, initialize.clone()).main_failed());
+
+    let mut recorder = Recorder::new(GtestTransport::new(&system, program, ADMIN), vec![]);
+
+    recorder
+        .write(&Record {
+            timestamp,
+            source: ADMIN.into(),
+            value: 0,
+            message: Message::Init(initialize, Ok(())),
+        })
+        .unwrap();
+
+    let mut client = Client::new(recorder);
+
+    client
+        .start(
+            DURATION,
+            PARTICIPATION_COST,
+            None,
+            Selection::Uniform,
+            None,
+            Pricing::Fixed,
+        )
+        .await
+        .unwrap();
+
+    for player in PLAYERS {
+        system.mint_to(player, PARTICIPATION_COST);
+        client.transport_mut().transport_mut().set_sender(player);
+        client.enter(None, PARTICIPATION_COST).await.unwrap();
+    }
+
+    system.spend_blocks((DURATION / 1000) as _);
+    client.transport_mut().transport_mut().set_sender(ADMIN);
+
+    let winner = client.pick_winner(None).await.unwrap();
+
+    assert!(matches!(winner, Event::Winner { .. }));
+
+    let log = String::from_utf8(client.transport().writer().clone()).unwrap();
+    let output = replay(log);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{stdout}");
+    assert!(
+        stdout.contains(&format!("{:?}", Ok::<_, Error>(winner))),
+        "{stdout}"
+    );
+}
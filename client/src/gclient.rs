@@ -0,0 +1,169 @@
+//! The [`Transport`] over a Gear node through [`gclient`].
+
+use super::Transport;
+use ::gclient::{Error as GclientError, Event as NodeEvent, EventListener, GearApi, GearEvent};
+use async_trait::async_trait;
+use core::fmt::{self, Display, Formatter};
+use game_of_chance_io::*;
+use gstd::ActorId;
+use parity_scale_codec::{Decode, Encode, Error as CodecError};
+
+/// The default multiplier for a calculated gas limit.
+///
+/// The contract may wait for replies from a fungible token contract, so the
+/// minimum calculated limit isn't always enough.
+pub const DEFAULT_GAS_MULTIPLIER: u64 = 10;
+
+/// A [`GclientTransport`] error.
+#[derive(Debug)]
+pub enum NodeError {
+    /// A node request failed.
+    Gclient(GclientError),
+    /// The contract replied with an error message instead of a reply, e.g.
+    /// because of a panic or gas exhaustion.
+    ErrorReply(String),
+    /// Failed to decode a reply.
+    Decode(CodecError),
+    /// The timestamp of a block with the last reply wasn't read, because
+    /// another block was added after it, or nothing was sent yet.
+    TimestampUnavailable,
+}
+
+impl Display for NodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gclient(error) => write!(f, "node request failed: {error}"),
+            Self::ErrorReply(error) => write!(f, "received an error message: {error}"),
+            Self::Decode(error) => write!(f, "failed to decode a reply: {error}"),
+            Self::TimestampUnavailable => {
+                f.write_str("the timestamp of a block with the last reply is unavailable")
+            }
+        }
+    }
+}
+
+impl std::error::Error for NodeError {}
+
+impl From<GclientError> for NodeError {
+    fn from(error: GclientError) -> Self {
+        Self::Gclient(error)
+    }
+}
+
+/// The [`Transport`] over a Gear node through [`gclient`].
+///
+/// Actions are sent from the account of `api`.
+pub struct GclientTransport {
+    api: GearApi,
+    listener: EventListener,
+    program: ActorId,
+    gas_multiplier: u64,
+    reply_timestamp: Option<u64>,
+}
+
+impl GclientTransport {
+    pub async fn new(api: GearApi, program: impl Into<ActorId>) -> Result<Self, NodeError> {
+        let listener = api.subscribe().await?;
+
+        Ok(Self {
+            api,
+            listener,
+            program: program.into(),
+            gas_multiplier: DEFAULT_GAS_MULTIPLIER,
+            reply_timestamp: None,
+        })
+    }
+
+    pub fn api(&self) -> &GearApi {
+        &self.api
+    }
+
+    pub fn program(&self) -> ActorId {
+        self.program
+    }
+
+    /// Sets a multiplier for a calculated gas limit of each action.
+    ///
+    /// The default one is [`DEFAULT_GAS_MULTIPLIER`].
+    pub fn with_gas_multiplier(mut self, gas_multiplier: u64) -> Self {
+        self.gas_multiplier = gas_multiplier;
+
+        self
+    }
+}
+
+#[async_trait(?Send)]
+impl Transport for GclientTransport {
+    type Error = NodeError;
+
+    async fn send(
+        &mut self,
+        action: Action,
+        value: u128,
+    ) -> Result<Result<Event, Error>, Self::Error> {
+        let destination = <[u8; 32]>::from(self.program).into();
+        let gas_limit = self
+            .api
+            .calculate_handle_gas(None, destination, action.encode(), value, true)
+            .await?
+            .min_limit
+            .saturating_mul(self.gas_multiplier);
+        let (message_id, _) = self
+            .api
+            .send_message(destination, action, gas_limit, value)
+            .await?;
+        let (_, raw_reply, _) = self.listener.reply_bytes_on(message_id).await?;
+
+        self.reply_timestamp = reply_timestamp(&self.api, message_id).await?;
+
+        Decode::decode(&mut raw_reply.map_err(NodeError::ErrorReply)?.as_slice())
+            .map_err(NodeError::Decode)
+    }
+
+    async fn state(&self) -> Result<State, Self::Error> {
+        Ok(self
+            .api
+            .read_state(<[u8; 32]>::from(self.program).into())
+            .await?)
+    }
+
+    fn source(&self) -> ActorId {
+        <[u8; 32]>::from(self.api.account_id().clone()).into()
+    }
+
+    // The contract replies in the block where it finished processing an
+    // action.
+    async fn block_timestamp(&self) -> Result<u64, Self::Error> {
+        self.reply_timestamp.ok_or(NodeError::TimestampUnavailable)
+    }
+}
+
+/// Reads the timestamp of a block with a reply to `message_id`.
+///
+/// [`GearApi`] reads the timestamp of the last block only, so it should be
+/// called right after the reply is received. [`None`] means that the last
+/// block isn't the one with the reply.
+pub async fn reply_timestamp(
+    api: &GearApi,
+    message_id: impl AsRef<[u8]>,
+) -> Result<Option<u64>, NodeError> {
+    let block_hash = api.last_block_hash().await?;
+    let timestamp = api.last_block_timestamp().await?;
+
+    // Makes sure the timestamp is read from the same block.
+    if api.last_block_hash().await? != block_hash {
+        return Ok(None);
+    }
+
+    let has_reply = api.events_at(block_hash).await?.into_iter().any(|event| {
+        matches!(
+            event,
+            NodeEvent::Gear(GearEvent::UserMessageSent { message, .. })
+                if message
+                    .details
+                    .map_or(false, |details| details.to.0[..] == *message_id.as_ref())
+        )
+    });
+
+    Ok(has_reply.then_some(timestamp))
+}
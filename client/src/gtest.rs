@@ -0,0 +1,130 @@
+//! The [`Transport`] over an in-process [`gtest`] program.
+
+use super::Transport;
+use ::gtest::{Program, RunResult, System};
+use async_trait::async_trait;
+use core::fmt::{self, Display, Formatter};
+use game_of_chance_io::*;
+use gstd::ActorId;
+use parity_scale_codec::{Decode, Error as CodecError};
+
+/// A [`GtestTransport`] error.
+#[derive(Debug)]
+pub enum GtestError {
+    /// The contract panicked while processing an action.
+    MainFailed,
+    /// The contract didn't reply to an action.
+    ReplyIsMissing,
+    /// Failed to decode a reply.
+    Decode(CodecError),
+    /// Failed to read the state.
+    State(::gtest::Error),
+}
+
+impl Display for GtestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MainFailed => f.write_str("the contract panicked"),
+            Self::ReplyIsMissing => f.write_str("the contract didn't reply"),
+            Self::Decode(error) => write!(f, "failed to decode a reply: {error}"),
+            Self::State(error) => write!(f, "failed to read the state: {error:?}"),
+        }
+    }
+}
+
+impl std::error::Error for GtestError {}
+
+/// The [`Transport`] over an in-process [`gtest`] program.
+///
+/// All actions are sent from `sender`, which can be changed with
+/// [`GtestTransport::set_sender()`].
+pub struct GtestTransport<'a> {
+    system: &'a System,
+    program: Program<'a>,
+    sender: ActorId,
+    last_result: Option<RunResult>,
+}
+
+impl<'a> GtestTransport<'a> {
+    pub fn new(system: &'a System, program: Program<'a>, sender: impl Into<ActorId>) -> Self {
+        Self {
+            system,
+            program,
+            sender: sender.into(),
+            last_result: None,
+        }
+    }
+
+    pub fn program(&self) -> &Program<'a> {
+        &self.program
+    }
+
+    pub fn sender(&self) -> ActorId {
+        self.sender
+    }
+
+    pub fn set_sender(&mut self, sender: impl Into<ActorId>) {
+        self.sender = sender.into();
+    }
+
+    /// A raw result of the last sent action, e.g. to inspect messages sent by
+    /// the contract.
+    ///
+    /// Also available if the action failed with a [`GtestError`].
+    pub fn last_result(&self) -> Option<&RunResult> {
+        self.last_result.as_ref()
+    }
+
+    /// Takes the raw result of the last sent action out of the transport.
+    pub fn take_last_result(&mut self) -> Option<RunResult> {
+        self.last_result.take()
+    }
+}
+
+#[async_trait(?Send)]
+impl Transport for GtestTransport<'_> {
+    type Error = GtestError;
+
+    async fn send(
+        &mut self,
+        action: Action,
+        value: u128,
+    ) -> Result<Result<Event, Error>, Self::Error> {
+        let sender: [u8; 32] = self.sender.into();
+        let result = self
+            .last_result
+            .insert(self.program.send_with_value(sender, action, value));
+
+        if result.main_failed() {
+            return Err(GtestError::MainFailed);
+        }
+
+        let program_id = self.program.id();
+
+        // The contract replies at the end of processing, so the reply is the
+        // last message sent to `sender`.
+        result
+            .log()
+            .iter()
+            .filter(|log| {
+                log.source() == program_id && log.destination().as_ref() == sender.as_ref()
+            })
+            .last()
+            .map(|log| Decode::decode(&mut log.payload()))
+            .ok_or(GtestError::ReplyIsMissing)?
+            .map_err(GtestError::Decode)
+    }
+
+    async fn state(&self) -> Result<State, Self::Error> {
+        self.program.read_state().map_err(GtestError::State)
+    }
+
+    fn source(&self) -> ActorId {
+        self.sender
+    }
+
+    // `gtest` processes messages in the current block.
+    async fn block_timestamp(&self) -> Result<u64, Self::Error> {
+        Ok(self.system.block_timestamp())
+    }
+}
@@ -0,0 +1,209 @@
+//! A typed client for the Game of chance contract.
+//!
+//! [`Client`] encodes [`Action`]s, decodes [`Result<Event, Error>`] replies,
+//! and reads [`State`] over a [`Transport`]. Transports are provided for
+//! [`gtest`](crate::gtest) (with the `gtest` feature) and
+//! [`gclient`](crate::gclient) (with the `gclient` feature), so the same code
+//! runs in tests and against a node.
+//!
+//! Sent actions can be recorded with [`Recorder`](record::Recorder) to be
+//! replayed later.
+
+use async_trait::async_trait;
+use core::fmt::{self, Debug, Display, Formatter};
+use game_of_chance_io::*;
+use gstd::ActorId;
+
+#[cfg(feature = "gclient")]
+pub mod gclient;
+#[cfg(feature = "gtest")]
+pub mod gtest;
+pub mod record;
+
+pub use game_of_chance_io as io;
+
+/// A way to deliver [`Action`]s to the contract & read its [`State`].
+#[async_trait(?Send)]
+pub trait Transport {
+    /// A transport error.
+    type Error: Debug + Display;
+
+    /// Sends `action` with `value` attached and waits for a reply.
+    async fn send(
+        &mut self,
+        action: Action,
+        value: u128,
+    ) -> Result<Result<Event, Error>, Self::Error>;
+
+    /// Reads the contract state.
+    async fn state(&self) -> Result<State, Self::Error>;
+
+    /// The sender of actions.
+    fn source(&self) -> ActorId;
+
+    /// The timestamp of a block in which the last sent action was processed.
+    async fn block_timestamp(&self) -> Result<u64, Self::Error>;
+}
+
+/// A client error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientError<E> {
+    /// The transport failed to deliver an action or read the state.
+    Transport(E),
+    /// The contract processed an action and replied with an error.
+    Contract(Error),
+}
+
+impl<E: Display> Display for ClientError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport(error) => write!(f, "transport error: {error}"),
+            Self::Contract(error) => write!(f, "contract error: {error:?}"),
+        }
+    }
+}
+
+impl<E: Debug + Display> std::error::Error for ClientError<E> {}
+
+/// A result of a [`Client`] method.
+pub type ClientResult<T, E> = Result<T, ClientError<E>>;
+
+/// A typed client for the Game of chance contract.
+pub struct Client<T> {
+    transport: T,
+}
+
+impl<T: Transport> Client<T> {
+    pub fn new(transport: T) -> Self {
+        Self { transport }
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    pub fn into_transport(self) -> T {
+        self.transport
+    }
+
+    /// Sends any `action` with `value` attached.
+    pub async fn send(&mut self, action: Action, value: u128) -> ClientResult<Event, T::Error> {
+        self.transport
+            .send(action, value)
+            .await
+            .map_err(ClientError::Transport)?
+            .map_err(ClientError::Contract)
+    }
+
+    /// Sends [`Action::Start`].
+    pub async fn start(
+        &mut self,
+        duration: u64,
+        participation_cost: u128,
+        fungible_token: Option<ActorId>,
+        selection: Selection,
+        raffle: Option<Raffle>,
+        pricing: Pricing,
+    ) -> ClientResult<Event, T::Error> {
+        self.send(
+            Action::Start {
+                duration,
+                participation_cost,
+                fungible_token,
+                selection,
+                raffle,
+                pricing,
+            },
+            0,
+        )
+        .await
+    }
+
+    /// Sends [`Action::Enter`].
+    ///
+    /// `value` should be equal to the price (see [`State::price`]) if the
+    /// current game round currency is the native value, and 0 otherwise.
+    pub async fn enter(
+        &mut self,
+        round: Option<u64>,
+        value: u128,
+    ) -> ClientResult<Event, T::Error> {
+        self.send(Action::Enter(round), value).await
+    }
+
+    /// Sends [`Action::PickWinner`].
+    pub async fn pick_winner(&mut self, round: Option<u64>) -> ClientResult<Event, T::Error> {
+        self.send(Action::PickWinner(round), 0).await
+    }
+
+    /// Sends [`Action::RecoverValue`].
+    pub async fn recover_value(
+        &mut self,
+        to: impl Into<ActorId>,
+        amount: u128,
+    ) -> ClientResult<Event, T::Error> {
+        self.send(
+            Action::RecoverValue {
+                to: to.into(),
+                amount,
+            },
+            0,
+        )
+        .await
+    }
+
+    /// Sends [`Action::ReserveGas`].
+    pub async fn reserve_gas(
+        &mut self,
+        amount: u64,
+        duration: u32,
+    ) -> ClientResult<Event, T::Error> {
+        self.send(Action::ReserveGas { amount, duration }, 0).await
+    }
+
+    /// Sends [`Action::GasReservation`].
+    pub async fn gas_reservation(&mut self) -> ClientResult<Event, T::Error> {
+        self.send(Action::GasReservation, 0).await
+    }
+
+    /// Sends [`Action::ConfigureTokenCalls`].
+    pub async fn configure_token_calls(
+        &mut self,
+        reply_deposit: u64,
+        reply_timeout: Option<u32>,
+    ) -> ClientResult<Event, T::Error> {
+        self.send(
+            Action::ConfigureTokenCalls {
+                reply_deposit,
+                reply_timeout,
+            },
+            0,
+        )
+        .await
+    }
+
+    /// Sends [`Action::Subscribe`].
+    pub async fn subscribe(
+        &mut self,
+        subscriber: impl Into<ActorId>,
+    ) -> ClientResult<Event, T::Error> {
+        self.send(Action::Subscribe(subscriber.into()), 0).await
+    }
+
+    /// Sends [`Action::Unsubscribe`].
+    pub async fn unsubscribe(
+        &mut self,
+        subscriber: impl Into<ActorId>,
+    ) -> ClientResult<Event, T::Error> {
+        self.send(Action::Unsubscribe(subscriber.into()), 0).await
+    }
+
+    /// Reads the contract state.
+    pub async fn state(&self) -> ClientResult<State, T::Error> {
+        self.transport.state().await.map_err(ClientError::Transport)
+    }
+}
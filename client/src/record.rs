@@ -0,0 +1,208 @@
+//! Recording of messages sent to the contract for a later replay.
+//!
+//! # Log format
+//!
+//! A log is a text file where each line is a 0x-prefixed hex string of a
+//! SCALE-encoded [`Record`]. Empty lines & lines starting with `#` are
+//! comments. A log that can be replayed from scratch starts with a record of
+//! [`Message::Init`], other records contain [`Message::Handle`] in the order
+//! of processing.
+//!
+//! ```text
+//! # Game of chance, recorded by `game-of-chance-cli`.
+//! 0x...
+//! 0x...
+//! ```
+//!
+//! Timestamps are taken from the block where a message was processed, so
+//! replaying a log reproduces the checks of the entry stage ending. If a
+//! transport fails to read it, the record still is written with the timestamp
+//! of the previous record (or 0 if there's none) as a lower bound, and it's
+//! preceded by a comment about that.
+
+use super::Transport;
+use async_trait::async_trait;
+use core::fmt::{self, Display, Formatter};
+use game_of_chance_io::*;
+use gstd::{prelude::*, ActorId};
+use parity_scale_codec::Error as CodecError;
+use std::io::{self, Write};
+
+/// A recorded message with its context & reply.
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// The timestamp of a block in which the message was processed.
+    pub timestamp: u64,
+    pub source: ActorId,
+    pub value: u128,
+    pub message: Message,
+}
+
+/// A recorded message & reply.
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Init(Initialize, Result<(), Error>),
+    Handle(Action, Result<Event, Error>),
+}
+
+/// A failure of parsing a log line.
+#[derive(Debug)]
+pub enum ParseError {
+    /// A line isn't a 0x-prefixed hex string.
+    Hex,
+    /// A line doesn't contain a [`Record`].
+    Decode(CodecError),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hex => f.write_str("a record isn't a 0x-prefixed hex string"),
+            Self::Decode(error) => write!(f, "failed to decode a record: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Record {
+    /// Encodes the record into a log line without a line break.
+    pub fn to_line(&self) -> String {
+        format!("0x{}", hex::encode(self.encode()))
+    }
+
+    /// Decodes a record from a log line.
+    pub fn from_line(line: &str) -> Result<Self, ParseError> {
+        let bytes = line
+            .trim()
+            .strip_prefix("0x")
+            .and_then(|line| hex::decode(line).ok())
+            .ok_or(ParseError::Hex)?;
+
+        Self::decode(&mut bytes.as_slice()).map_err(ParseError::Decode)
+    }
+}
+
+/// Parses all records of a log, skipping comments, with numbers of their
+/// lines.
+pub fn parse(log: &str) -> impl Iterator<Item = (usize, Result<Record, ParseError>)> + '_ {
+    log.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| (number, Record::from_line(line)))
+}
+
+/// A [`Recorder`] error.
+#[derive(Debug)]
+pub enum RecorderError<E> {
+    Transport(E),
+    /// Failed to write a record.
+    Io(io::Error),
+}
+
+impl<E: Display> Display for RecorderError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport(error) => Display::fmt(error, f),
+            Self::Io(error) => write!(f, "failed to write a record: {error}"),
+        }
+    }
+}
+
+/// The [`Transport`] that writes a [`Record`] of every sent action & its
+/// reply to `writer`.
+pub struct Recorder<T, W> {
+    transport: T,
+    writer: W,
+    /// The timestamp of the last written record, used as a lower bound of an
+    /// unavailable one.
+    timestamp: u64,
+}
+
+impl<T: Transport, W: Write> Recorder<T, W> {
+    pub fn new(transport: T, writer: W) -> Self {
+        Self {
+            transport,
+            writer,
+            timestamp: 0,
+        }
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    /// Writes `record` to the log, e.g. a record of [`Message::Init`].
+    pub fn write(&mut self, record: &Record) -> io::Result<()> {
+        writeln!(self.writer, "{}", record.to_line())?;
+        self.timestamp = record.timestamp;
+        self.writer.flush()
+    }
+}
+
+#[async_trait(?Send)]
+impl<T: Transport, W: Write> Transport for Recorder<T, W> {
+    type Error = RecorderError<T::Error>;
+
+    async fn send(
+        &mut self,
+        action: Action,
+        value: u128,
+    ) -> Result<Result<Event, Error>, Self::Error> {
+        let reply = self
+            .transport
+            .send(action, value)
+            .await
+            .map_err(RecorderError::Transport)?;
+        // The action is already processed, so its record is written even if
+        // the timestamp is unavailable, and only then the error is returned.
+        let timestamp = self.block_timestamp().await;
+
+        if let Err(error) = &timestamp {
+            writeln!(
+                self.writer,
+                "# {error}, so the timestamp of the next record is a lower bound"
+            )
+            .map_err(RecorderError::Io)?;
+        }
+
+        let record = Record {
+            timestamp: *timestamp.as_ref().unwrap_or(&self.timestamp),
+            source: self.source(),
+            value,
+            message: Message::Handle(action, reply.clone()),
+        };
+
+        self.write(&record).map_err(RecorderError::Io)?;
+        timestamp?;
+
+        Ok(reply)
+    }
+
+    async fn state(&self) -> Result<State, Self::Error> {
+        self.transport
+            .state()
+            .await
+            .map_err(RecorderError::Transport)
+    }
+
+    fn source(&self) -> ActorId {
+        self.transport.source()
+    }
+
+    async fn block_timestamp(&self) -> Result<u64, Self::Error> {
+        self.transport
+            .block_timestamp()
+            .await
+            .map_err(RecorderError::Transport)
+    }
+}
@@ -0,0 +1,1324 @@
+//! The game logic of the Game of chance contract.
+//!
+//! [`Contract`] doesn't depend on the Gear runtime. It reads the context of a
+//! processed action from an [`Environment`] and asks it to send messages, so
+//! the game logic can be tested natively and embedded in off-chain
+//! simulators. The contract itself is a thin adapter of it to `gstd`.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+    vec,
+    vec::Vec,
+};
+use core::{cmp::Reverse, future::Future, iter, mem, pin::Pin};
+#[cfg(feature = "testing")]
+use game_of_chance_io::testing::*;
+use game_of_chance_io::*;
+use hashbrown::HashMap;
+
+const MAX_NUMBER_OF_TXS: usize = 2usize.pow(16);
+/// The number of blocks a settlement reservation outlives the players entry
+/// stage.
+const SETTLEMENT_RESERVATION_MARGIN: u32 = 14_400;
+/// The number of blocks a reservation left from a previous game round must
+/// outlive the players entry stage to be reused for a settlement.
+const MIN_SETTLEMENT_RESERVATION_MARGIN: u32 = SETTLEMENT_RESERVATION_MARGIN / 2;
+const MAX_SETTLEMENT_RESERVATION_DURATION: u32 = 2_592_000;
+
+/// Players ordered by their winnings in each currency.
+type Leaderboards = BTreeMap<Option<ActorId>, BTreeSet<(Reverse<u128>, ActorId)>>;
+
+/// A message that [`Contract`] sends without waiting for a reply.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Effect {
+    /// The native value sent to an actor.
+    Value { to: ActorId, value: u128 },
+    /// A [`Notification`] sent to a subscriber, or to the user-visible event
+    /// stream if it's sent to [`ActorId::zero()`].
+    Notification {
+        to: ActorId,
+        notification: Notification,
+    },
+}
+
+/// A transfer of FTs that [`Contract`] requests from an FT contract.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TokenTransfer {
+    pub fungible_token: ActorId,
+    /// An ID by which the FT contract recognizes a retried transfer, so it
+    /// doesn't execute it twice.
+    pub transaction_id: u64,
+    pub sender: ActorId,
+    pub recipient: ActorId,
+    pub amount: u128,
+    /// The amount of gas reserved for handling a reply.
+    pub reply_deposit: u64,
+    /// The number of blocks to wait for a reply. [`None`] means no timeout.
+    pub reply_timeout: Option<u32>,
+}
+
+/// A reply of an FT contract to a [`TokenTransfer`].
+pub type TokenReply<'a> = Pin<Box<dyn Future<Output = Result<(), Error>> + 'a>>;
+
+/// The context of a processed action & the outside world for [`Contract`].
+pub trait Environment {
+    /// An ID of a gas reservation.
+    type Reservation: Copy;
+
+    /// The sender of a processed action.
+    fn source(&self) -> ActorId;
+
+    /// The contract itself.
+    fn program_id(&self) -> ActorId;
+
+    /// The native value attached to a processed action.
+    fn value(&self) -> u128;
+
+    /// The native value the contract owns.
+    fn value_available(&self) -> u128;
+
+    fn block_timestamp(&self) -> u64;
+
+    fn block_height(&self) -> u32;
+
+    /// A seed for picking winners with [`Selection::pick()`].
+    fn random_seed(&self) -> u64;
+
+    /// Reserves `amount` of gas for `duration` blocks.
+    fn reserve_gas(&mut self, amount: u64, duration: u32) -> Result<Self::Reservation, Error>;
+
+    /// Sends `effect`, paying for it from `reservation` if it's given.
+    fn send(&mut self, effect: Effect, reservation: Option<Self::Reservation>)
+        -> Result<(), Error>;
+
+    /// Sends `transfer` to an FT contract, paying for it from `reservation`
+    /// if it's given.
+    ///
+    /// A reply must resolve to `Ok(())`, to [`Error::TokenTransferFailed`] if
+    /// the FT contract rejected the transfer, or to
+    /// [`Error::UnexpectedTokenReply`] if it replied with something else. Any
+    /// other error means the transfer may still be executed, so it's retried
+    /// with the same [`TokenTransfer::transaction_id`].
+    fn transfer_tokens(
+        &mut self,
+        transfer: TokenTransfer,
+        reservation: Option<Self::Reservation>,
+    ) -> TokenReply<'_>;
+}
+
+/// The contract state & the game logic.
+#[derive(Debug)]
+pub struct Contract<R> {
+    admin: ActorId,
+
+    fungible_token: Option<ActorId>,
+    started: u64,
+    ending: u64,
+    players: Vec<ActorId>,
+    stakes: Vec<u128>,
+    prize_fund: u128,
+    participation_cost: u128,
+    status: RoundStatus,
+    selection: Selection,
+    raffle: Option<Raffle>,
+    pricing: Pricing,
+
+    winners: Vec<ActorId>,
+    /// The number of `winners` whose prizes are sent.
+    paid_winners: usize,
+    /// The number of `players` whose participation costs are refunded.
+    refunded_players: usize,
+
+    keys_for_tx: BTreeMap<u64, TxKey>,
+    txs_for_key: HashMap<TxKey, u64>,
+    /// Prices of [`Action::Enter`] whose transfer transactions are cached.
+    entry_prices: HashMap<ActorId, u128>,
+    /// Players of [`Action::Enter`] whose transfers are awaited with block
+    /// heights until which they're awaited. They hold places until the
+    /// transfers are replied or overdue.
+    pending_entries: BTreeMap<ActorId, u32>,
+    tx_id_nonce: u64,
+    token_reply_deposit: u64,
+    token_reply_timeout: Option<u32>,
+
+    existential_deposit: u128,
+    dust: u128,
+
+    reservations: Vec<Reservation<R>>,
+
+    round: u64,
+    subscribers: Vec<ActorId>,
+
+    player_stats: BTreeMap<ActorId, PlayerStats>,
+    players_by_activity: BTreeSet<(u64, ActorId)>,
+    /// Maintained along with `player_stats`, so [`State::leaderboards`]
+    /// aren't sorted on every state query.
+    leaderboards: Leaderboards,
+    notification_nonce: u64,
+
+    #[cfg(feature = "testing")]
+    randomness_override: Option<RandomnessOverride>,
+}
+
+/// A key of a cached FT transfer transaction.
+///
+/// Settlement transfers are keyed per game round & recipient index, so they
+/// never share a transaction with each other or with players entries.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+enum TxKey {
+    /// A transfer of [`Action::Enter`] from a player.
+    Entry(ActorId),
+    /// A prize transfer to the winner with the index.
+    Prize { round: u64, winner: usize },
+    /// A refund to the player with the index.
+    Refund { round: u64, player: usize },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Reservation<R> {
+    id: R,
+    amount: u64,
+    expiration: u32,
+}
+
+impl<R: Copy> Contract<R> {
+    /// Initializes the contract.
+    pub fn new(
+        Initialize {
+            admin,
+            existential_deposit,
+        }: Initialize,
+    ) -> Result<Self, Error> {
+        if admin.is_zero() {
+            return Err(Error::ZeroActorId);
+        }
+
+        Ok(Self {
+            admin,
+            fungible_token: None,
+            started: 0,
+            ending: 0,
+            players: vec![],
+            stakes: vec![],
+            prize_fund: 0,
+            participation_cost: 0,
+            status: RoundStatus::NotStarted,
+            selection: Selection::default(),
+            raffle: None,
+            pricing: Pricing::default(),
+            winners: vec![],
+            paid_winners: 0,
+            refunded_players: 0,
+            keys_for_tx: BTreeMap::new(),
+            txs_for_key: HashMap::new(),
+            entry_prices: HashMap::new(),
+            pending_entries: BTreeMap::new(),
+            tx_id_nonce: 0,
+            token_reply_deposit: 0,
+            token_reply_timeout: Some(DEFAULT_TOKEN_REPLY_TIMEOUT),
+            existential_deposit,
+            dust: 0,
+            reservations: vec![],
+            round: 0,
+            subscribers: vec![],
+            player_stats: BTreeMap::new(),
+            players_by_activity: BTreeSet::new(),
+            leaderboards: BTreeMap::new(),
+            notification_nonce: 0,
+            #[cfg(feature = "testing")]
+            randomness_override: None,
+        })
+    }
+
+    /// Processes `action`, and notifies about it if it changes the contract
+    /// state.
+    pub async fn handle(
+        &mut self,
+        env: &mut impl Environment<Reservation = R>,
+        action: Action,
+    ) -> Result<Event, Error> {
+        let is_query = matches!(action, Action::GasReservation);
+
+        let result = match action {
+            Action::Start {
+                duration,
+                participation_cost,
+                fungible_token,
+                selection,
+                raffle,
+                pricing,
+            } => self.start(
+                env,
+                duration,
+                participation_cost,
+                fungible_token,
+                selection,
+                raffle,
+                pricing,
+            ),
+            Action::PickWinner(round) => self.pick_winner(env, round).await,
+            Action::Enter(round) => self.enter(env, round).await,
+            Action::RecoverValue { to, amount } => self.recover_value(env, to, amount),
+            Action::ReserveGas { amount, duration } => {
+                self.top_up_gas_reservation(env, amount, duration)
+            }
+            Action::GasReservation => self.gas_reservation(env),
+            Action::ConfigureTokenCalls {
+                reply_deposit,
+                reply_timeout,
+            } => self.configure_token_calls(env, reply_deposit, reply_timeout),
+            Action::Subscribe(subscriber) => self.subscribe(env, subscriber),
+            Action::Unsubscribe(subscriber) => self.unsubscribe(env, subscriber),
+        };
+
+        if let (Ok(event), false) = (&result, is_query) {
+            self.notify(env, event.clone());
+        }
+
+        result
+    }
+
+    /// Processes a test hook. Unlike actions, test hooks aren't notified about.
+    #[cfg(feature = "testing")]
+    pub fn handle_test(
+        &mut self,
+        env: &impl Environment<Reservation = R>,
+        action: TestAction,
+    ) -> Result<TestEvent, Error> {
+        match action {
+            TestAction::OverrideRandomness(randomness_override) => {
+                self.override_randomness(env, randomness_override)
+            }
+        }
+    }
+
+    /// The contract state.
+    pub fn state(&self, env: &impl Environment<Reservation = R>) -> State {
+        State {
+            admin: self.admin,
+            fungible_token: self.fungible_token,
+            started: self.started,
+            ending: self.ending,
+            players: self.players.clone(),
+            stakes: self.stakes.clone(),
+            prize_fund: self.prize_fund,
+            participation_cost: self.participation_cost,
+            winner: self.winners.first().copied(),
+            winners: self.winners.clone(),
+            selection: self.selection,
+            raffle: self.raffle,
+            pricing: self.pricing,
+            price: self.price(env.block_timestamp(), env.block_height()),
+            status: self.status(env.block_timestamp()),
+            reserved_gas: self.reserved_gas(env.block_height()),
+            token_reply_deposit: self.token_reply_deposit,
+            token_reply_timeout: self.token_reply_timeout,
+            existential_deposit: self.existential_deposit,
+            dust: self.dust,
+            subscribers: self.subscribers.clone(),
+            round: self.round,
+            player_stats: self
+                .player_stats
+                .iter()
+                .map(|(player, stats)| (*player, stats.clone()))
+                .collect(),
+            leaderboards: self
+                .leaderboards
+                .iter()
+                .map(|(currency, players)| {
+                    (
+                        *currency,
+                        players
+                            .iter()
+                            .map(|(Reverse(won), player)| (*player, *won))
+                            .collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn start(
+        &mut self,
+        env: &mut impl Environment<Reservation = R>,
+        duration: u64,
+        participation_cost: u128,
+        fungible_token: Option<ActorId>,
+        selection: Selection,
+        raffle: Option<Raffle>,
+        pricing: Pricing,
+    ) -> Result<Event, Error> {
+        if self.admin != env.source() {
+            return Err(Error::AccessRestricted);
+        }
+
+        if !matches!(
+            self.status,
+            RoundStatus::NotStarted | RoundStatus::Finished | RoundStatus::Cancelled
+        ) {
+            return Err(Error::RoundIsActive {
+                status: self.status(env.block_timestamp()),
+                ending: self.ending,
+            });
+        }
+
+        if matches!(fungible_token, Some(fungible_token) if fungible_token.is_zero()) {
+            return Err(Error::ZeroActorId);
+        }
+
+        if fungible_token.is_some() && self.token_reply_timeout().is_none() {
+            return Err(Error::InfiniteTokenReplyTimeout);
+        }
+
+        if selection == Selection::Multiple(0) {
+            return Err(Error::InvalidSelection);
+        }
+
+        if let Some(Raffle { tickets, .. }) = raffle {
+            if tickets == 0 || tickets as usize > MAX_NUMBER_OF_PLAYERS {
+                return Err(Error::InvalidRaffle);
+            }
+        }
+
+        if !pricing.is_valid(participation_cost) {
+            return Err(Error::InvalidPricing);
+        }
+
+        // The contract doesn't know the block time, so the duration is
+        // converted as if a block is produced every second. This only
+        // overestimates the reservation lifetime on slower chains.
+        let reservation_duration = u32::try_from(duration / 1000)
+            .unwrap_or(u32::MAX)
+            .saturating_add(SETTLEMENT_RESERVATION_MARGIN)
+            .min(MAX_SETTLEMENT_RESERVATION_DURATION);
+
+        let block_height = env.block_height();
+        let min_expiration = block_height
+            .saturating_add(reservation_duration)
+            .saturating_sub(SETTLEMENT_RESERVATION_MARGIN - MIN_SETTLEMENT_RESERVATION_MARGIN);
+
+        self.remove_expired_reservations(block_height);
+
+        // A reservation left unused by a previous game round, e.g. a cancelled
+        // one, is reused if it's big enough & lives long enough, so they don't
+        // pile up.
+        if !self.reservations.iter().any(|reservation| {
+            reservation.amount >= SETTLEMENT_GAS && reservation.expiration >= min_expiration
+        }) {
+            self.reserve_gas(env, SETTLEMENT_GAS, reservation_duration)?;
+        }
+
+        self.players.clear();
+        self.stakes.clear();
+        self.winners.clear();
+
+        self.round = self.round.wrapping_add(1);
+        self.paid_winners = 0;
+        self.refunded_players = 0;
+        self.prize_fund = 0;
+        self.started = env.block_timestamp();
+        self.ending = self.started.saturating_add(duration);
+        self.participation_cost = participation_cost;
+        self.fungible_token = fungible_token;
+        self.selection = selection;
+        self.raffle = raffle;
+        self.pricing = pricing;
+        self.status = RoundStatus::Entry;
+
+        // TODO: uncomment and update doc & tests after closing
+        // https://github.com/gear-tech/gear/issues/1781.
+        // msg::send_delayed(
+        //     exec::program_id(),
+        //     Action::PickWinner,
+        //     0,
+        //     (duration / 1000) as u32,
+        // )?;
+
+        Ok(Event::Started {
+            round: self.round,
+            ending: self.ending,
+            participation_cost,
+            fungible_token,
+            selection,
+            raffle,
+            pricing,
+        })
+    }
+
+    async fn pick_winner(
+        &mut self,
+        env: &mut impl Environment<Reservation = R>,
+        round: Option<u64>,
+    ) -> Result<Event, Error> {
+        self.check_round(round)?;
+
+        match self.status {
+            RoundStatus::Entry | RoundStatus::AwaitingDraw | RoundStatus::Settling => {}
+            RoundStatus::NotStarted => return Err(Error::NoRoundStarted),
+            RoundStatus::Finished => {
+                return Err(Error::WinnerAlreadyPicked {
+                    winner: self.winners.first().copied().unwrap_or_default(),
+                })
+            }
+            RoundStatus::Cancelled => return Err(Error::RoundIsCancelled),
+        }
+
+        let msg_source = env.source();
+        let exec_program = env.program_id();
+        let block_timestamp = env.block_timestamp();
+
+        if msg_source == self.admin {
+            if self.ending > block_timestamp && !self.is_sold_out() {
+                return Err(Error::EntryStageNotOver {
+                    ending: self.ending,
+                    now: block_timestamp,
+                });
+            }
+        } else if msg_source != exec_program {
+            return Err(Error::AccessRestricted);
+        }
+
+        self.remove_overdue_entries(env.block_height());
+
+        // Players of pending entries may have paid already, so they can't be
+        // left out of a draw or refunds.
+        if !self.pending_entries.is_empty() {
+            return Err(Error::EntriesPending);
+        }
+
+        if self.winners.is_empty() {
+            if self.players.is_empty() {
+                self.status = RoundStatus::Cancelled;
+
+                return Ok(Event::Cancelled { round: self.round });
+            }
+
+            if let Some(Raffle {
+                unsold: UnsoldTickets::Refund,
+                ..
+            }) = self.raffle
+            {
+                if !self.is_sold_out() {
+                    return self.refund_players(env).await;
+                }
+            }
+
+            // Winners are cached, so a failed settlement can be retried
+            // without picking other ones.
+            self.winners = self
+                .draw(env.random_seed())?
+                .into_iter()
+                .map(|index| self.players[index])
+                .collect();
+            self.status = RoundStatus::Settling;
+        }
+
+        let (prizes, dust) = if let Some(fungible_token) = self.fungible_token {
+            let prizes = split(self.prize_fund, self.winners.len());
+            // A reservation can be used only for one message, so only the
+            // first transfer is sent from it.
+            let mut reservation = self.take_reservation(env.block_height());
+
+            // Winners are paid one by one, so if a transfer fails, only the
+            // remaining ones are paid on a retry.
+            while let Some(&winner) = self.winners.get(self.paid_winners) {
+                self.transfer_tokens(
+                    env,
+                    fungible_token,
+                    TxKey::Prize {
+                        round: self.round,
+                        winner: self.paid_winners,
+                    },
+                    exec_program,
+                    winner,
+                    prizes[self.paid_winners],
+                    reservation.take(),
+                )
+                .await?;
+
+                self.paid_winners += 1;
+            }
+
+            (prizes, 0)
+        } else {
+            // The dust left from previous game rounds is carried over to the
+            // current prize fund.
+            let payout = self.prize_fund.saturating_add(self.dust);
+            let mut prizes = split(payout, self.winners.len());
+            // Only prizes less than the existential deposit are held back, so
+            // other winners are still paid.
+            let dust = prizes
+                .iter_mut()
+                .filter(|prize| **prize < self.existential_deposit)
+                .fold(0u128, |dust, prize| dust.saturating_add(mem::take(prize)));
+            let mut reservation = if dust == payout {
+                None
+            } else {
+                self.take_reservation(env.block_height())
+            };
+
+            while let Some(&winner) = self.winners.get(self.paid_winners) {
+                let prize = prizes[self.paid_winners];
+
+                if prize != 0 {
+                    env.send(
+                        Effect::Value {
+                            to: winner,
+                            value: prize,
+                        },
+                        reservation.take(),
+                    )?;
+                }
+
+                self.paid_winners += 1;
+            }
+
+            self.dust = dust;
+
+            (prizes, dust)
+        };
+
+        self.status = RoundStatus::Finished;
+
+        let (round, currency) = (self.round, self.fungible_token);
+        let winners: Vec<_> = self.winners.iter().copied().zip(prizes).collect();
+
+        for &(winner, prize) in &winners {
+            self.update_player_stats(winner, |stats| {
+                stats.wins = stats.wins.saturating_add(1);
+                stats.last_round = round;
+
+                let currency_stats = stats.currencies.entry(currency).or_default();
+
+                currency_stats.won = currency_stats.won.saturating_add(prize);
+            });
+        }
+
+        Ok(if let Selection::Multiple(_) = self.selection {
+            Event::Winners {
+                round,
+                winners,
+                dust,
+            }
+        } else {
+            let (winner, prize) = winners[0];
+
+            Event::Winner {
+                round,
+                winner,
+                prize,
+                dust,
+            }
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn transfer_tokens(
+        &mut self,
+        env: &mut impl Environment<Reservation = R>,
+        fungible_token: ActorId,
+        key: TxKey,
+        sender: ActorId,
+        recipient: ActorId,
+        amount: u128,
+        reservation: Option<R>,
+    ) -> Result<(), Error> {
+        let transaction_id = if let Some(id) = self.txs_for_key.get(&key) {
+            *id
+        } else {
+            let id = self.tx_id_nonce;
+
+            self.tx_id_nonce = id.wrapping_add(1);
+
+            if self.keys_for_tx.len() == MAX_NUMBER_OF_TXS {
+                let (tx, evicted) = self
+                    .keys_for_tx
+                    .range(self.tx_id_nonce..)
+                    .next()
+                    .unwrap_or_else(|| {
+                        let key_value = self.keys_for_tx.first_key_value();
+
+                        debug_assert!(key_value.is_some(), "tx cache cycle is corrupted, perhaps the `MAX_NUMBER_OF_TXS` constant is less than 2");
+
+                        unsafe { key_value.unwrap_unchecked() }
+                    });
+                let (tx, evicted) = (*tx, *evicted);
+
+                self.keys_for_tx.remove(&tx);
+                self.remove_tx_key(evicted);
+            }
+
+            self.keys_for_tx.insert(id, key);
+            self.txs_for_key.insert(key, id);
+
+            id
+        };
+
+        let result = env
+            .transfer_tokens(
+                TokenTransfer {
+                    fungible_token,
+                    transaction_id,
+                    sender,
+                    recipient,
+                    amount,
+                    reply_deposit: self.token_reply_deposit,
+                    reply_timeout: self.token_reply_timeout(),
+                },
+                reservation,
+            )
+            .await;
+
+        // The transaction stays cached on a failed delivery or a timeout, so the
+        // same transaction ID will be used on a next attempt, and the FT
+        // contract won't execute the transfer twice.
+        if matches!(
+            result,
+            Ok(()) | Err(Error::TokenTransferFailed | Error::UnexpectedTokenReply)
+        ) {
+            self.keys_for_tx.remove(&transaction_id);
+            self.remove_tx_key(key);
+        }
+
+        result
+    }
+
+    fn remove_tx_key(&mut self, key: TxKey) {
+        self.txs_for_key.remove(&key);
+
+        if let TxKey::Entry(player) = key {
+            self.entry_prices.remove(&player);
+        }
+    }
+
+    async fn enter(
+        &mut self,
+        env: &mut impl Environment<Reservation = R>,
+        round: Option<u64>,
+    ) -> Result<Event, Error> {
+        self.check_round(round)?;
+
+        if self.round == 0 {
+            return Err(Error::NoRoundStarted);
+        }
+
+        let (block_timestamp, block_height) = (env.block_timestamp(), env.block_height());
+
+        self.remove_overdue_entries(block_height);
+
+        let entries = self.players.len() + self.pending_entries.len();
+
+        if matches!(self.raffle, Some(Raffle { tickets, .. }) if entries >= tickets as usize) {
+            return Err(Error::SoldOut);
+        }
+
+        if self.ending <= block_timestamp {
+            return Err(Error::EntryStageOver {
+                ending: self.ending,
+                now: block_timestamp,
+            });
+        }
+
+        if entries >= MAX_NUMBER_OF_PLAYERS {
+            return Err(Error::MemoryLimitExceeded {
+                limit: Limit::Players,
+                maximum: MAX_NUMBER_OF_PLAYERS as u32,
+            });
+        }
+
+        let msg_source = env.source();
+
+        if self.players.contains(&msg_source) || self.pending_entries.contains_key(&msg_source) {
+            return Err(Error::AlreadyParticipating);
+        }
+
+        let mut price = self.price(block_timestamp, block_height);
+
+        if let Some(fungible_token) = self.fungible_token {
+            let program_id = env.program_id();
+
+            // The FT contract executes a retried transaction with the amount of
+            // the first attempt, so the price it was sent with is kept.
+            if let Some(entry_price) = self.entry_prices.get(&msg_source) {
+                price = *entry_price;
+            }
+
+            // Other actions are executed while the transfer is awaited, so the
+            // place is held for the player, and the round can't be settled
+            // until it's released. Game rounds in fungible tokens have a finite
+            // reply timeout, so the place is released by the deadline even if
+            // the message is never woken up.
+            let deadline = self
+                .token_reply_timeout()
+                .map_or(u32::MAX, |timeout| block_height.saturating_add(timeout));
+
+            self.pending_entries.insert(msg_source, deadline);
+
+            let result = self
+                .transfer_tokens(
+                    env,
+                    fungible_token,
+                    TxKey::Entry(msg_source),
+                    msg_source,
+                    program_id,
+                    price,
+                    None,
+                )
+                .await;
+
+            self.pending_entries.remove(&msg_source);
+
+            if self.txs_for_key.contains_key(&TxKey::Entry(msg_source)) {
+                self.entry_prices.insert(msg_source, price);
+            }
+
+            result?;
+        } else {
+            let msg_value = env.value();
+
+            if msg_value < price {
+                self.refund(env, msg_source, msg_value)?;
+
+                return Err(Error::InvalidParticipationCost {
+                    expected: price,
+                    sent: msg_value,
+                });
+            }
+
+            // The price may change after the action is sent, so an overpayment
+            // is accepted.
+            self.refund(env, msg_source, msg_value - price)?;
+        }
+
+        self.players.push(msg_source);
+        self.stakes.push(price);
+        self.prize_fund = self.prize_fund.saturating_add(price);
+
+        let (round, currency) = (self.round, self.fungible_token);
+
+        self.update_player_stats(msg_source, |stats| {
+            stats.rounds_entered = stats.rounds_entered.saturating_add(1);
+            stats.last_round = round;
+
+            let currency_stats = stats.currencies.entry(currency).or_default();
+
+            currency_stats.spent = currency_stats.spent.saturating_add(price);
+        });
+
+        Ok(Event::PlayerAdded {
+            round: self.round,
+            player: msg_source,
+        })
+    }
+
+    /// The status of the current game round at `now`.
+    ///
+    /// [`RoundStatus::AwaitingDraw`] isn't stored because the contract isn't
+    /// notified about the end of the players entry stage.
+    fn status(&self, now: u64) -> RoundStatus {
+        match self.status {
+            RoundStatus::Entry if self.ending <= now || self.is_sold_out() => {
+                RoundStatus::AwaitingDraw
+            }
+            status => status,
+        }
+    }
+
+    /// The price of the next entry into the current game round at `now` &
+    /// `block_height`.
+    ///
+    /// Held places count as entries, so concurrent entries don't pay the same
+    /// price.
+    fn price(&self, now: u64, block_height: u32) -> u128 {
+        let pending_entries = self
+            .pending_entries
+            .values()
+            .filter(|deadline| **deadline >= block_height)
+            .count();
+
+        self.pricing.price(
+            self.participation_cost,
+            self.ending.saturating_sub(self.started),
+            (self.players.len() + pending_entries) as u32,
+            now.saturating_sub(self.started),
+        )
+    }
+
+    /// Releases places of entries whose transfers are awaited after their
+    /// deadlines.
+    ///
+    /// By then, such a transfer has timed out, or its message has been removed
+    /// from the waitlist, so the entry won't be completed.
+    fn remove_overdue_entries(&mut self, block_height: u32) {
+        self.pending_entries
+            .retain(|_, deadline| *deadline >= block_height);
+    }
+
+    /// [`Self::token_reply_timeout`] with zero meaning no timeout too.
+    fn token_reply_timeout(&self) -> Option<u32> {
+        self.token_reply_timeout.filter(|timeout| *timeout != 0)
+    }
+
+    /// Whether all tickets of the current [`Raffle`] are sold.
+    fn is_sold_out(&self) -> bool {
+        matches!(self.raffle, Some(Raffle { tickets, .. }) if self.players.len() >= tickets as usize)
+    }
+
+    /// Sends participation costs back to players of a [`Raffle`] with unsold
+    /// tickets, and cancels the game round.
+    async fn refund_players(
+        &mut self,
+        env: &mut impl Environment<Reservation = R>,
+    ) -> Result<Event, Error> {
+        self.status = RoundStatus::Settling;
+
+        let exec_program = env.program_id();
+        let mut reservation = self.take_reservation(env.block_height());
+
+        // Players are refunded one by one, so if a refund fails, only the
+        // remaining ones are refunded on a retry.
+        while let Some(&player) = self.players.get(self.refunded_players) {
+            let stake = self.stakes[self.refunded_players];
+
+            if let Some(fungible_token) = self.fungible_token {
+                self.transfer_tokens(
+                    env,
+                    fungible_token,
+                    TxKey::Refund {
+                        round: self.round,
+                        player: self.refunded_players,
+                    },
+                    exec_program,
+                    player,
+                    stake,
+                    reservation.take(),
+                )
+                .await?;
+            } else if stake < self.existential_deposit {
+                self.dust = self.dust.saturating_add(stake);
+            } else if stake != 0 {
+                env.send(
+                    Effect::Value {
+                        to: player,
+                        value: stake,
+                    },
+                    reservation.take(),
+                )?;
+            }
+
+            self.refunded_players += 1;
+
+            let currency = self.fungible_token;
+
+            self.update_player_stats(player, |stats| {
+                let currency_stats = stats.currencies.entry(currency).or_default();
+
+                currency_stats.spent = currency_stats.spent.saturating_sub(stake);
+            });
+        }
+
+        self.status = RoundStatus::Cancelled;
+
+        let (amount, dust) = self
+            .stakes
+            .iter()
+            .fold((0u128, 0u128), |(amount, dust), &stake| {
+                if self.fungible_token.is_none() && stake < self.existential_deposit {
+                    (amount, dust.saturating_add(stake))
+                } else {
+                    (amount.saturating_add(stake), dust)
+                }
+            });
+
+        Ok(Event::Refunded {
+            round: self.round,
+            amount,
+            dust,
+        })
+    }
+
+    /// Updates lifetime statistics of `player`.
+    ///
+    /// If the number of tracked players reaches
+    /// [`MAX_NUMBER_OF_TRACKED_PLAYERS`], the least recently active one is
+    /// evicted to track a new one.
+    fn update_player_stats(&mut self, player: ActorId, update: impl FnOnce(&mut PlayerStats)) {
+        if let Some(stats) = self.player_stats.get(&player) {
+            self.players_by_activity.remove(&(stats.last_round, player));
+            unrank(&mut self.leaderboards, player, stats);
+        } else if self.player_stats.len() == MAX_NUMBER_OF_TRACKED_PLAYERS {
+            if let Some((_, evicted)) = self.players_by_activity.pop_first() {
+                if let Some(stats) = self.player_stats.remove(&evicted) {
+                    unrank(&mut self.leaderboards, evicted, &stats);
+                }
+            }
+        }
+
+        let stats = self.player_stats.entry(player).or_default();
+
+        update(stats);
+        self.players_by_activity.insert((stats.last_round, player));
+        rank(&mut self.leaderboards, player, stats);
+    }
+
+    /// Picks indexes of winners among players.
+    fn draw(&self, seed: u64) -> Result<Vec<usize>, Error> {
+        #[cfg(feature = "testing")]
+        match self.randomness_override {
+            Some(RandomnessOverride::Seed(seed)) => {
+                return Ok(self.selection.pick(seed, &self.stakes))
+            }
+            Some(RandomnessOverride::Winner(index)) => {
+                let index = index as usize;
+                let number_of_winners = match self.selection {
+                    Selection::Multiple(number) => self.players.len().min(number as usize),
+                    Selection::Uniform | Selection::Weighted => 1,
+                };
+
+                return if number_of_winners == 1 && index < self.players.len() {
+                    Ok(vec![index])
+                } else {
+                    Err(Error::InvalidSelection)
+                };
+            }
+            None => {}
+        }
+
+        Ok(self.selection.pick(seed, &self.stakes))
+    }
+
+    #[cfg(feature = "testing")]
+    fn override_randomness(
+        &mut self,
+        env: &impl Environment<Reservation = R>,
+        randomness_override: Option<RandomnessOverride>,
+    ) -> Result<TestEvent, Error> {
+        if self.admin != env.source() {
+            return Err(Error::AccessRestricted);
+        }
+
+        self.randomness_override = randomness_override;
+
+        Ok(TestEvent::RandomnessOverridden(randomness_override))
+    }
+
+    fn check_round(&self, round: Option<u64>) -> Result<(), Error> {
+        match round {
+            Some(expected) if expected != self.round => Err(Error::UnexpectedRound {
+                expected,
+                current: self.round,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn recover_value(
+        &mut self,
+        env: &mut impl Environment<Reservation = R>,
+        to: ActorId,
+        amount: u128,
+    ) -> Result<Event, Error> {
+        if self.admin != env.source() {
+            return Err(Error::AccessRestricted);
+        }
+
+        if to.is_zero() {
+            return Err(Error::ZeroActorId);
+        }
+
+        let surplus = self.surplus(env.value_available());
+
+        if amount > surplus {
+            return Err(Error::InsufficientSurplus { surplus });
+        }
+
+        if amount < self.existential_deposit {
+            return Err(Error::BelowExistentialDeposit {
+                existential_deposit: self.existential_deposit,
+            });
+        }
+
+        env.send(Effect::Value { to, value: amount }, None)?;
+
+        Ok(Event::ValueRecovered { to, amount })
+    }
+
+    fn top_up_gas_reservation(
+        &mut self,
+        env: &mut impl Environment<Reservation = R>,
+        amount: u64,
+        duration: u32,
+    ) -> Result<Event, Error> {
+        if self.admin != env.source() {
+            return Err(Error::AccessRestricted);
+        }
+
+        let expiration = self.reserve_gas(env, amount, duration)?;
+
+        Ok(Event::GasReserved { amount, expiration })
+    }
+
+    fn gas_reservation(&mut self, env: &impl Environment<Reservation = R>) -> Result<Event, Error> {
+        if self.admin != env.source() {
+            return Err(Error::AccessRestricted);
+        }
+
+        let block_height = env.block_height();
+
+        self.remove_expired_reservations(block_height);
+
+        Ok(Event::GasReservation {
+            reserved_gas: self.reserved_gas(block_height),
+            expiration: self
+                .reservations
+                .iter()
+                .map(|reservation| reservation.expiration)
+                .min(),
+        })
+    }
+
+    fn reserve_gas(
+        &mut self,
+        env: &mut impl Environment<Reservation = R>,
+        amount: u64,
+        duration: u32,
+    ) -> Result<u32, Error> {
+        let block_height = env.block_height();
+
+        self.remove_expired_reservations(block_height);
+
+        if self.reservations.len() == MAX_NUMBER_OF_RESERVATIONS {
+            return Err(Error::MemoryLimitExceeded {
+                limit: Limit::Reservations,
+                maximum: MAX_NUMBER_OF_RESERVATIONS as u32,
+            });
+        }
+
+        let id = env.reserve_gas(amount, duration)?;
+        let expiration = block_height.saturating_add(duration);
+
+        self.reservations.push(Reservation {
+            id,
+            amount,
+            expiration,
+        });
+
+        Ok(expiration)
+    }
+
+    /// Takes the soonest expiring reservation to send a settlement from it.
+    ///
+    /// A reservation can be used only once, so it's removed even if the
+    /// settlement fails afterwards.
+    fn take_reservation(&mut self, block_height: u32) -> Option<R> {
+        self.remove_expired_reservations(block_height);
+
+        let (index, _) = self
+            .reservations
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, reservation)| reservation.expiration)?;
+
+        Some(self.reservations.swap_remove(index).id)
+    }
+
+    fn remove_expired_reservations(&mut self, block_height: u32) {
+        self.reservations
+            .retain(|reservation| reservation.expiration > block_height);
+    }
+
+    fn reserved_gas(&self, block_height: u32) -> u64 {
+        self.reservations
+            .iter()
+            .filter(|reservation| reservation.expiration > block_height)
+            .fold(0, |reserved_gas, reservation| {
+                reserved_gas.saturating_add(reservation.amount)
+            })
+    }
+
+    fn configure_token_calls(
+        &mut self,
+        env: &impl Environment<Reservation = R>,
+        reply_deposit: u64,
+        reply_timeout: Option<u32>,
+    ) -> Result<Event, Error> {
+        if self.admin != env.source() {
+            return Err(Error::AccessRestricted);
+        }
+
+        let is_active = matches!(self.status, RoundStatus::Entry | RoundStatus::Settling);
+
+        if self.fungible_token.is_some()
+            && is_active
+            && reply_timeout.filter(|timeout| *timeout != 0).is_none()
+        {
+            return Err(Error::InfiniteTokenReplyTimeout);
+        }
+
+        self.token_reply_deposit = reply_deposit;
+        self.token_reply_timeout = reply_timeout;
+
+        Ok(Event::TokenCallsConfigured {
+            reply_deposit,
+            reply_timeout,
+        })
+    }
+
+    fn subscribe(
+        &mut self,
+        env: &impl Environment<Reservation = R>,
+        subscriber: ActorId,
+    ) -> Result<Event, Error> {
+        if self.admin != env.source() {
+            return Err(Error::AccessRestricted);
+        }
+
+        if subscriber.is_zero() {
+            return Err(Error::ZeroActorId);
+        }
+
+        if self.subscribers.contains(&subscriber) {
+            return Err(Error::AlreadySubscribed);
+        }
+
+        if self.subscribers.len() == MAX_NUMBER_OF_SUBSCRIBERS {
+            return Err(Error::MemoryLimitExceeded {
+                limit: Limit::Subscribers,
+                maximum: MAX_NUMBER_OF_SUBSCRIBERS as u32,
+            });
+        }
+
+        self.subscribers.push(subscriber);
+
+        Ok(Event::Subscribed(subscriber))
+    }
+
+    fn unsubscribe(
+        &mut self,
+        env: &impl Environment<Reservation = R>,
+        subscriber: ActorId,
+    ) -> Result<Event, Error> {
+        if self.admin != env.source() {
+            return Err(Error::AccessRestricted);
+        }
+
+        let index = self
+            .subscribers
+            .iter()
+            .position(|actor| *actor == subscriber)
+            .ok_or(Error::NotSubscribed)?;
+
+        self.subscribers.remove(index);
+
+        Ok(Event::Unsubscribed(subscriber))
+    }
+
+    /// Broadcasts `event` to the subscribers & the user-visible event stream.
+    fn notify(&mut self, env: &mut impl Environment<Reservation = R>, event: Event) {
+        let notification = Notification {
+            round: self.round,
+            sequence: self.notification_nonce,
+            timestamp: env.block_timestamp(),
+            event,
+        };
+
+        self.notification_nonce = self.notification_nonce.wrapping_add(1);
+
+        // Messages to `ActorId::zero()` appear in the user-visible event
+        // stream.
+        for to in iter::once(ActorId::zero()).chain(self.subscribers.iter().copied()) {
+            // Notifications are best-effort, so a failed one mustn't fail an
+            // already processed action.
+            let _ = env.send(
+                Effect::Notification {
+                    to,
+                    notification: notification.clone(),
+                },
+                None,
+            );
+        }
+    }
+
+    /// Sends the native value back, or adds it to the dust if it's less than
+    /// the existential deposit.
+    fn refund(
+        &mut self,
+        env: &mut impl Environment<Reservation = R>,
+        recipient: ActorId,
+        value: u128,
+    ) -> Result<(), Error> {
+        if value == 0 {
+            return Ok(());
+        }
+
+        if value < self.existential_deposit {
+            self.dust = self.dust.saturating_add(value);
+        } else {
+            env.send(
+                Effect::Value {
+                    to: recipient,
+                    value,
+                },
+                None,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// The native value that isn't owed to anyone out of `value_available`.
+    fn surplus(&self, value_available: u128) -> u128 {
+        let is_unsettled = matches!(self.status, RoundStatus::Entry | RoundStatus::Settling);
+        let liabilities = if is_unsettled && self.fungible_token.is_none() {
+            self.prize_fund.saturating_add(self.dust)
+        } else {
+            self.dust
+        };
+
+        value_available.saturating_sub(liabilities)
+    }
+}
+
+/// Adds winnings of `player` to `leaderboards`.
+fn rank(leaderboards: &mut Leaderboards, player: ActorId, stats: &PlayerStats) {
+    for (currency, currency_stats) in &stats.currencies {
+        if currency_stats.won != 0 {
+            leaderboards
+                .entry(*currency)
+                .or_default()
+                .insert((Reverse(currency_stats.won), player));
+        }
+    }
+}
+
+/// Removes winnings of `player` added by [`rank()`] from `leaderboards`.
+fn unrank(leaderboards: &mut Leaderboards, player: ActorId, stats: &PlayerStats) {
+    for (currency, currency_stats) in &stats.currencies {
+        if let Some(players) = leaderboards.get_mut(currency) {
+            players.remove(&(Reverse(currency_stats.won), player));
+
+            if players.is_empty() {
+                leaderboards.remove(currency);
+            }
+        }
+    }
+}
+
+/// Splits `amount` into `parts` equal parts, adding the remainder of the
+/// division to the first one.
+fn split(amount: u128, parts: usize) -> Vec<u128> {
+    let parts = parts as u128;
+    let part = amount / parts;
+    let first = part + amount % parts;
+
+    iter::once(first)
+        .chain(iter::repeat(part))
+        .take(parts as usize)
+        .collect()
+}
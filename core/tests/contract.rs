@@ -0,0 +1,633 @@
+use game_of_chance_core::Contract;
+use game_of_chance_io::*;
+use utils::*;
+
+mod utils;
+
+#[test]
+fn zero_admin() {
+    assert_eq!(
+        Contract::<u32>::new(Initialize {
+            admin: ActorId::zero(),
+            existential_deposit: 0,
+        })
+        .unwrap_err(),
+        Error::ZeroActorId
+    );
+}
+
+#[test]
+fn native_round() {
+    const PARTICIPATION_COST: u128 = 10_000;
+
+    let mut goc = Goc::initialize();
+
+    assert_eq!(
+        goc.start(2000, PARTICIPATION_COST, None),
+        Ok(Event::Started {
+            round: 1,
+            ending: 2000,
+            participation_cost: PARTICIPATION_COST,
+            fungible_token: None,
+            selection: Selection::Uniform,
+            raffle: None,
+            pricing: Pricing::Fixed,
+        })
+    );
+
+    for player in PLAYERS {
+        assert_eq!(
+            goc.send(player, Action::Enter(Some(1)), PARTICIPATION_COST),
+            Ok(Event::PlayerAdded {
+                round: 1,
+                player: player.into(),
+            })
+        );
+    }
+
+    goc.env.spend_blocks(2);
+    goc.env.random_seed = 42;
+    goc.env.effects.clear();
+
+    let winner = ActorId::from(PLAYERS[winner_index(42, PLAYERS.len())]);
+    let prize = PARTICIPATION_COST * PLAYERS.len() as u128;
+
+    assert_eq!(
+        goc.send(ADMIN, Action::PickWinner(Some(1)), 0),
+        Ok(Event::Winner {
+            round: 1,
+            winner,
+            prize,
+            dust: 0,
+        })
+    );
+    // The prize is sent from the reservation made by `Action::Start`.
+    assert_eq!(goc.env.take_values(), [(winner, prize, Some(1))]);
+
+    let state = goc.state();
+
+    assert_eq!(state.winner, Some(winner));
+    assert_eq!(state.status, RoundStatus::Finished);
+}
+
+#[test]
+fn mixed_prizes() {
+    let mut goc = Goc::initialize();
+
+    goc.send(
+        ADMIN,
+        Action::Start {
+            duration: 2000,
+            participation_cost: EXISTENTIAL_DEPOSIT - 1,
+            fungible_token: None,
+            selection: Selection::Multiple(2),
+            raffle: None,
+            pricing: Pricing::Linear { step: 1 },
+        },
+        0,
+    )
+    .unwrap();
+
+    let stakes = [EXISTENTIAL_DEPOSIT - 1, EXISTENTIAL_DEPOSIT];
+
+    for (player, stake) in PLAYERS.into_iter().zip(stakes) {
+        goc.send(player, Action::Enter(None), stake).unwrap();
+    }
+
+    goc.env.spend_blocks(2);
+    goc.env.effects.clear();
+
+    // The prize fund is split into `EXISTENTIAL_DEPOSIT` for the first winner
+    // & `EXISTENTIAL_DEPOSIT - 1` for the second one, so only the second prize
+    // is held back.
+    let winners: Vec<_> = Selection::Multiple(2)
+        .pick(0, &stakes)
+        .into_iter()
+        .map(|index| ActorId::from(PLAYERS[index]))
+        .collect();
+
+    assert_eq!(
+        goc.send(ADMIN, Action::PickWinner(None), 0),
+        Ok(Event::Winners {
+            round: 1,
+            winners: vec![(winners[0], EXISTENTIAL_DEPOSIT), (winners[1], 0)],
+            dust: EXISTENTIAL_DEPOSIT - 1,
+        })
+    );
+    assert_eq!(
+        goc.env.take_values(),
+        [(winners[0], EXISTENTIAL_DEPOSIT, Some(1))]
+    );
+    assert_eq!(goc.state().dust, EXISTENTIAL_DEPOSIT - 1);
+}
+
+#[test]
+fn weighted_selection() {
+    const ROUNDS: u64 = 1000;
+
+    let mut second_player_wins = 0;
+
+    for seed in 0..ROUNDS {
+        let mut goc = Goc::initialize();
+
+        // The second player pays 9 times more than the first one.
+        goc.send(
+            ADMIN,
+            Action::Start {
+                duration: 2000,
+                participation_cost: 1000,
+                fungible_token: None,
+                selection: Selection::Weighted,
+                raffle: None,
+                pricing: Pricing::Linear { step: 8000 },
+            },
+            0,
+        )
+        .unwrap();
+        goc.send(PLAYERS[0], Action::Enter(None), 1000).unwrap();
+        goc.send(PLAYERS[1], Action::Enter(None), 9000).unwrap();
+        goc.env.spend_blocks(2);
+        goc.env.random_seed = seed;
+        goc.send(ADMIN, Action::PickWinner(None), 0).unwrap();
+
+        if goc.state().winner == Some(PLAYERS[1].into()) {
+            second_player_wins += 1;
+        }
+    }
+
+    // The expected number is 900 with the standard deviation of ~9.5.
+    assert!(
+        (850..=950).contains(&second_player_wins),
+        "{second_player_wins} wins of {ROUNDS}"
+    );
+}
+
+#[test]
+fn leaderboard() {
+    fn win(goc: &mut Goc, player: u64, prize: u128) {
+        goc.start(2000, prize, None).unwrap();
+        goc.send(player, Action::Enter(None), prize).unwrap();
+        goc.env.spend_blocks(2);
+        goc.send(ADMIN, Action::PickWinner(None), 0).unwrap();
+    }
+
+    fn leaderboard(goc: &Goc) -> Vec<(ActorId, u128)> {
+        let mut leaderboards = goc.state().leaderboards;
+
+        assert_eq!(leaderboards.len(), 1);
+
+        let (currency, leaderboard) = leaderboards.remove(0);
+
+        assert_eq!(currency, None);
+
+        leaderboard
+    }
+
+    let mut goc = Goc::initialize();
+    let [first, second, third] = PLAYERS.map(ActorId::from);
+
+    win(&mut goc, PLAYERS[1], 10_000);
+    win(&mut goc, PLAYERS[0], 10_000);
+    win(&mut goc, PLAYERS[2], 20_000);
+
+    // Equal winnings are ordered by players' IDs.
+    assert_eq!(
+        leaderboard(&goc),
+        [(third, 20_000), (first, 10_000), (second, 10_000)]
+    );
+
+    win(&mut goc, PLAYERS[1], 5000);
+
+    assert_eq!(
+        leaderboard(&goc),
+        [(third, 20_000), (second, 15_000), (first, 10_000)]
+    );
+}
+
+#[test]
+fn reservation_reuse() {
+    let mut goc = Goc::initialize();
+
+    // Cancelled game rounds leave their settlement reservations unused, so
+    // next ones reuse them instead of reserving more gas.
+    for round in 1..=100 {
+        goc.start(2000, 10_000, None).unwrap();
+        goc.env.spend_blocks(2);
+
+        assert_eq!(
+            goc.send(ADMIN, Action::PickWinner(None), 0),
+            Ok(Event::Cancelled { round })
+        );
+    }
+
+    assert_eq!(goc.env.reservations, 1);
+
+    // A reservation that doesn't live long enough isn't reused.
+    goc.start(u32::MAX as u64 * 1000, 10_000, None).unwrap();
+
+    assert_eq!(goc.env.reservations, 2);
+}
+
+#[test]
+fn refund() {
+    let mut goc = Goc::initialize();
+
+    goc.start(2000, 10_000, None).unwrap();
+    goc.env.effects.clear();
+
+    assert_eq!(
+        goc.send(PLAYERS[0], Action::Enter(None), 9000),
+        Err(Error::InvalidParticipationCost {
+            expected: 10_000,
+            sent: 9000,
+        })
+    );
+    assert_eq!(goc.env.take_values(), [(PLAYERS[0].into(), 9000, None)]);
+
+    // Values less than the existential deposit are carried over in the dust.
+    assert_eq!(
+        goc.send(PLAYERS[0], Action::Enter(None), EXISTENTIAL_DEPOSIT - 1),
+        Err(Error::InvalidParticipationCost {
+            expected: 10_000,
+            sent: EXISTENTIAL_DEPOSIT - 1,
+        })
+    );
+    assert!(goc.env.take_values().is_empty());
+    assert_eq!(goc.state().dust, EXISTENTIAL_DEPOSIT - 1);
+}
+
+#[test]
+fn overpaid_dutch_auction_entry() {
+    let mut goc = Goc::initialize();
+
+    goc.send(
+        ADMIN,
+        Action::Start {
+            duration: 4000,
+            participation_cost: 10_000,
+            fungible_token: None,
+            selection: Selection::Uniform,
+            raffle: None,
+            pricing: Pricing::DutchAuction { floor: 6000 },
+        },
+        0,
+    )
+    .unwrap();
+    goc.env.effects.clear();
+    goc.env.spend_blocks(2);
+
+    // The price dropped after the player sent the starting one, so the
+    // difference is sent back.
+    goc.send(PLAYERS[0], Action::Enter(None), 10_000).unwrap();
+    assert_eq!(goc.env.take_values(), [(PLAYERS[0].into(), 2000, None)]);
+
+    let state = goc.state();
+
+    assert_eq!(state.stakes, [8000]);
+    assert_eq!(state.prize_fund, 8000);
+    assert_eq!(state.dust, 0);
+}
+
+#[test]
+fn overpaid_bonding_curve_entry() {
+    let mut goc = Goc::initialize();
+
+    goc.send(
+        ADMIN,
+        Action::Start {
+            duration: 2000,
+            participation_cost: 10_000,
+            fungible_token: None,
+            selection: Selection::Uniform,
+            raffle: None,
+            pricing: Pricing::Linear { step: 1000 },
+        },
+        0,
+    )
+    .unwrap();
+    goc.env.effects.clear();
+
+    // The player paid for a price raised by another entry that didn't happen.
+    goc.send(PLAYERS[0], Action::Enter(None), 11_000).unwrap();
+    assert_eq!(goc.env.take_values(), [(PLAYERS[0].into(), 1000, None)]);
+
+    // An excess less than the existential deposit is carried over in the dust.
+    goc.send(
+        PLAYERS[1],
+        Action::Enter(None),
+        11_000 + EXISTENTIAL_DEPOSIT - 1,
+    )
+    .unwrap();
+    assert!(goc.env.take_values().is_empty());
+
+    let state = goc.state();
+
+    assert_eq!(state.stakes, [10_000, 11_000]);
+    assert_eq!(state.prize_fund, 21_000);
+    assert_eq!(state.dust, EXISTENTIAL_DEPOSIT - 1);
+}
+
+#[test]
+fn token_transfer_retry() {
+    let mut goc = Goc::initialize();
+
+    goc.start(2000, 10_000, Some(FUNGIBLE_TOKEN.into()))
+        .unwrap();
+    goc.env.token_replies.push_back(Err(Error::TokenTimeout));
+
+    assert_eq!(
+        goc.send(PLAYERS[0], Action::Enter(None), 0),
+        Err(Error::TokenTimeout)
+    );
+    assert!(goc.state().players.is_empty());
+
+    // Another player's transfer gets another transaction ID.
+    goc.send(PLAYERS[1], Action::Enter(None), 0).unwrap();
+    goc.send(PLAYERS[0], Action::Enter(None), 0).unwrap();
+
+    let transfers: Vec<_> = goc
+        .env
+        .transfers
+        .iter()
+        .map(|(transfer, _)| (transfer.transaction_id, transfer.sender))
+        .collect();
+
+    assert_eq!(
+        transfers,
+        [
+            (0, PLAYERS[0].into()),
+            (1, PLAYERS[1].into()),
+            (0, PLAYERS[0].into()),
+        ]
+    );
+
+    // A rejected or unexpectedly answered transfer isn't retried with the same
+    // transaction ID.
+    for error in [Error::TokenTransferFailed, Error::UnexpectedTokenReply] {
+        goc.env.token_replies.push_back(Err(error.clone()));
+
+        assert_eq!(goc.send(PLAYERS[2], Action::Enter(None), 0), Err(error));
+        assert!(!goc.state().players.contains(&PLAYERS[2].into()));
+    }
+
+    goc.send(PLAYERS[2], Action::Enter(None), 0).unwrap();
+
+    let transaction_ids: Vec<_> = goc.env.transfers[3..]
+        .iter()
+        .map(|(transfer, _)| transfer.transaction_id)
+        .collect();
+
+    assert_eq!(transaction_ids, [2, 3, 4]);
+}
+
+#[test]
+fn overdue_token_entry() {
+    let mut goc = Goc::initialize();
+    let fungible_token = Some(FUNGIBLE_TOKEN.into());
+    let configure = |reply_timeout| Action::ConfigureTokenCalls {
+        reply_deposit: 0,
+        reply_timeout,
+    };
+
+    goc.send(ADMIN, configure(None), 0).unwrap();
+    assert_eq!(
+        goc.start(2000, 10_000, fungible_token),
+        Err(Error::InfiniteTokenReplyTimeout)
+    );
+
+    goc.send(ADMIN, configure(Some(DEFAULT_TOKEN_REPLY_TIMEOUT)), 0)
+        .unwrap();
+    goc.start(2000, 10_000, fungible_token).unwrap();
+
+    for reply_timeout in [None, Some(0)] {
+        assert_eq!(
+            goc.send(ADMIN, configure(reply_timeout), 0),
+            Err(Error::InfiniteTokenReplyTimeout)
+        );
+    }
+
+    goc.env.unreplied_transfers = 1;
+
+    assert_eq!(goc.try_send(PLAYERS[0], Action::Enter(None), 0), None);
+    assert_eq!(
+        goc.send(PLAYERS[0], Action::Enter(None), 0),
+        Err(Error::AlreadyParticipating)
+    );
+
+    goc.env.spend_blocks(2);
+
+    assert_eq!(
+        goc.send(ADMIN, Action::PickWinner(None), 0),
+        Err(Error::EntriesPending)
+    );
+
+    // The place is held until the deadline.
+    goc.env.spend_blocks(DEFAULT_TOKEN_REPLY_TIMEOUT - 2);
+
+    assert_eq!(
+        goc.send(ADMIN, Action::PickWinner(None), 0),
+        Err(Error::EntriesPending)
+    );
+
+    goc.env.spend_blocks(1);
+
+    assert_eq!(
+        goc.send(ADMIN, Action::PickWinner(None), 0),
+        Ok(Event::Cancelled { round: 1 })
+    );
+}
+
+#[test]
+fn settlement_transfer_retry() {
+    let mut goc = Goc::initialize();
+
+    goc.send(
+        ADMIN,
+        Action::Start {
+            duration: 2000,
+            participation_cost: 10_000,
+            fungible_token: Some(FUNGIBLE_TOKEN.into()),
+            selection: Selection::Multiple(2),
+            raffle: None,
+            pricing: Pricing::Fixed,
+        },
+        0,
+    )
+    .unwrap();
+
+    // The game administrator's entry is cached separately from settlement
+    // transfers.
+    goc.env.token_replies.push_back(Err(Error::TokenTimeout));
+    assert_eq!(
+        goc.send(ADMIN, Action::Enter(None), 0),
+        Err(Error::TokenTimeout)
+    );
+
+    for player in &PLAYERS[..2] {
+        goc.send(*player, Action::Enter(None), 0).unwrap();
+    }
+
+    goc.env.spend_blocks(2);
+    goc.env
+        .token_replies
+        .extend([Ok(()), Err(Error::TokenTimeout)]);
+
+    assert_eq!(
+        goc.send(ADMIN, Action::PickWinner(None), 0),
+        Err(Error::TokenTimeout)
+    );
+    goc.send(ADMIN, Action::PickWinner(None), 0).unwrap();
+
+    // Each prize has its own transaction, and only the timed out one is
+    // retried with the same transaction ID.
+    let transfers: Vec<_> = goc.env.transfers[3..]
+        .iter()
+        .map(|(transfer, _)| (transfer.transaction_id, transfer.recipient))
+        .collect();
+    let winners = goc.state().winners;
+
+    assert_eq!(
+        transfers,
+        [(3, winners[0]), (4, winners[1]), (4, winners[1])]
+    );
+}
+
+#[test]
+fn notifications() {
+    const SUBSCRIBER: u64 = 50;
+
+    let mut goc = Goc::initialize();
+
+    goc.send(ADMIN, Action::Subscribe(SUBSCRIBER.into()), 0)
+        .unwrap();
+    goc.env.effects.clear();
+
+    let event = goc.start(2000, 0, None).unwrap();
+    let notifications = goc.env.take_notifications();
+    let recipients: Vec<_> = notifications.iter().map(|(to, _)| *to).collect();
+
+    assert_eq!(recipients, [ActorId::zero(), SUBSCRIBER.into()]);
+    assert_eq!(
+        notifications[0].1,
+        Notification {
+            round: 1,
+            sequence: 1,
+            timestamp: 0,
+            event,
+        }
+    );
+
+    // Queries & failed actions aren't notified about.
+    goc.send(ADMIN, Action::GasReservation, 0).unwrap();
+    goc.send(PLAYERS[0], Action::GasReservation, 0).unwrap_err();
+    assert!(goc.env.effects.is_empty());
+}
+
+#[test]
+fn raffle_refund() {
+    let mut goc = Goc::initialize();
+
+    goc.send(
+        ADMIN,
+        Action::Start {
+            duration: 2000,
+            participation_cost: EXISTENTIAL_DEPOSIT - 1,
+            fungible_token: None,
+            selection: Selection::Uniform,
+            raffle: Some(Raffle {
+                tickets: 3,
+                unsold: UnsoldTickets::Refund,
+            }),
+            pricing: Pricing::Fixed,
+        },
+        0,
+    )
+    .unwrap();
+
+    for player in &PLAYERS[..2] {
+        goc.send(*player, Action::Enter(None), EXISTENTIAL_DEPOSIT - 1)
+            .unwrap();
+    }
+
+    goc.env.spend_blocks(2);
+    goc.env.effects.clear();
+
+    // Refunds less than the existential deposit are carried over in the dust.
+    assert_eq!(
+        goc.send(ADMIN, Action::PickWinner(None), 0),
+        Ok(Event::Refunded {
+            round: 1,
+            amount: 0,
+            dust: (EXISTENTIAL_DEPOSIT - 1) * 2,
+        })
+    );
+    assert!(goc.env.take_values().is_empty());
+
+    let state = goc.state();
+
+    assert_eq!(state.status, RoundStatus::Cancelled);
+    assert_eq!(state.dust, (EXISTENTIAL_DEPOSIT - 1) * 2);
+}
+
+#[test]
+fn pricing() {
+    let mut goc = Goc::initialize();
+    let start = |pricing| Action::Start {
+        duration: 4000,
+        participation_cost: 10_000,
+        fungible_token: None,
+        selection: Selection::Uniform,
+        raffle: None,
+        pricing,
+    };
+
+    // Every entry raises the price by 15% rounded down.
+    goc.send(ADMIN, start(Pricing::Exponential { rate: 1500 }), 0)
+        .unwrap();
+
+    for (player, price) in PLAYERS.into_iter().zip([10_000, 11_500, 13_225]) {
+        assert_eq!(goc.state().price, price);
+        goc.send(player, Action::Enter(None), price).unwrap();
+    }
+
+    assert_eq!(goc.state().price, 15_208);
+    assert_eq!(goc.state().prize_fund, 34_725);
+
+    goc.env.spend_blocks(4);
+    goc.send(ADMIN, Action::PickWinner(None), 0).unwrap();
+
+    assert_eq!(
+        goc.send(
+            ADMIN,
+            start(Pricing::EarlyBird {
+                price: 10_001,
+                duration: 2000,
+            }),
+            0,
+        ),
+        Err(Error::InvalidPricing)
+    );
+    goc.send(
+        ADMIN,
+        start(Pricing::EarlyBird {
+            price: 5000,
+            duration: 2000,
+        }),
+        0,
+    )
+    .unwrap();
+    goc.send(PLAYERS[0], Action::Enter(None), 5000).unwrap();
+    goc.env.spend_blocks(2);
+
+    // The early-bird period is over.
+    assert_eq!(
+        goc.send(PLAYERS[1], Action::Enter(None), 5000),
+        Err(Error::InvalidParticipationCost {
+            expected: 10_000,
+            sent: 5000,
+        })
+    );
+
+    goc.send(PLAYERS[1], Action::Enter(None), 10_000).unwrap();
+    assert_eq!(goc.state().stakes, [5000, 10_000]);
+}
@@ -0,0 +1,210 @@
+#![allow(dead_code)]
+
+use futures::{executor, future, FutureExt};
+use game_of_chance_core::{Contract, Effect, Environment, TokenReply, TokenTransfer};
+use game_of_chance_io::*;
+use std::collections::VecDeque;
+
+pub const ADMIN: u64 = 1;
+pub const PLAYERS: [u64; 3] = [2, 3, 4];
+pub const PROGRAM: u64 = 100;
+pub const FUNGIBLE_TOKEN: u64 = 200;
+pub const EXISTENTIAL_DEPOSIT: u128 = 500;
+
+/// An [`Environment`] that records sent messages instead of sending them.
+#[derive(Debug)]
+pub struct MockEnvironment {
+    pub source: ActorId,
+    pub value: u128,
+    pub value_available: u128,
+    pub block_timestamp: u64,
+    pub block_height: u32,
+    pub random_seed: u64,
+
+    /// Sent effects with IDs of reservations they're sent from.
+    pub effects: Vec<(Effect, Option<u32>)>,
+    pub transfers: Vec<(TokenTransfer, Option<u32>)>,
+    /// Replies to next transfers. A transfer succeeds if there are none.
+    pub token_replies: VecDeque<Result<(), Error>>,
+    /// The number of next transfers that are never replied, before
+    /// `token_replies` are used.
+    pub unreplied_transfers: u32,
+    pub reservations: u32,
+}
+
+impl Default for MockEnvironment {
+    fn default() -> Self {
+        Self {
+            source: ADMIN.into(),
+            value: 0,
+            value_available: 0,
+            block_timestamp: 0,
+            block_height: 0,
+            random_seed: 0,
+            effects: vec![],
+            transfers: vec![],
+            token_replies: VecDeque::new(),
+            unreplied_transfers: 0,
+            reservations: 0,
+        }
+    }
+}
+
+impl MockEnvironment {
+    /// Takes sent native values, skipping notifications.
+    pub fn take_values(&mut self) -> Vec<(ActorId, u128, Option<u32>)> {
+        self.effects
+            .drain(..)
+            .filter_map(|(effect, reservation)| match effect {
+                Effect::Value { to, value } => Some((to, value, reservation)),
+                Effect::Notification { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Takes sent notifications with their recipients.
+    pub fn take_notifications(&mut self) -> Vec<(ActorId, Notification)> {
+        self.effects
+            .drain(..)
+            .filter_map(|(effect, _)| match effect {
+                Effect::Notification { to, notification } => Some((to, notification)),
+                Effect::Value { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Advances time by `blocks` 1 second blocks.
+    pub fn spend_blocks(&mut self, blocks: u32) {
+        self.block_height += blocks;
+        self.block_timestamp += blocks as u64 * 1000;
+    }
+}
+
+impl Environment for MockEnvironment {
+    type Reservation = u32;
+
+    fn source(&self) -> ActorId {
+        self.source
+    }
+
+    fn program_id(&self) -> ActorId {
+        PROGRAM.into()
+    }
+
+    fn value(&self) -> u128 {
+        self.value
+    }
+
+    fn value_available(&self) -> u128 {
+        self.value_available
+    }
+
+    fn block_timestamp(&self) -> u64 {
+        self.block_timestamp
+    }
+
+    fn block_height(&self) -> u32 {
+        self.block_height
+    }
+
+    fn random_seed(&self) -> u64 {
+        self.random_seed
+    }
+
+    fn reserve_gas(&mut self, _: u64, _: u32) -> Result<u32, Error> {
+        self.reservations += 1;
+
+        Ok(self.reservations)
+    }
+
+    fn send(&mut self, effect: Effect, reservation: Option<u32>) -> Result<(), Error> {
+        self.effects.push((effect, reservation));
+
+        Ok(())
+    }
+
+    fn transfer_tokens(
+        &mut self,
+        transfer: TokenTransfer,
+        reservation: Option<u32>,
+    ) -> TokenReply<'_> {
+        self.transfers.push((transfer, reservation));
+
+        if self.unreplied_transfers != 0 {
+            self.unreplied_transfers -= 1;
+
+            return Box::pin(future::pending());
+        }
+
+        let reply = self.token_replies.pop_front().unwrap_or(Ok(()));
+
+        Box::pin(async move { reply })
+    }
+}
+
+pub struct Goc {
+    pub contract: Contract<u32>,
+    pub env: MockEnvironment,
+}
+
+impl Goc {
+    pub fn initialize() -> Self {
+        Self {
+            contract: Contract::new(Initialize {
+                admin: ADMIN.into(),
+                existential_deposit: EXISTENTIAL_DEPOSIT,
+            })
+            .unwrap(),
+            env: MockEnvironment::default(),
+        }
+    }
+
+    /// Processes `action` from `from` with the attached `value`.
+    pub fn send(&mut self, from: u64, action: Action, value: u128) -> Result<Event, Error> {
+        self.env.source = from.into();
+        self.env.value = value;
+        self.env.value_available += value;
+
+        executor::block_on(self.contract.handle(&mut self.env, action))
+    }
+
+    /// Processes `action` like [`Goc::send()`], but returns [`None`] if it
+    /// awaits a reply that never arrives, like a message that's never woken
+    /// up.
+    pub fn try_send(
+        &mut self,
+        from: u64,
+        action: Action,
+        value: u128,
+    ) -> Option<Result<Event, Error>> {
+        self.env.source = from.into();
+        self.env.value = value;
+        self.env.value_available += value;
+
+        self.contract.handle(&mut self.env, action).now_or_never()
+    }
+
+    pub fn start(
+        &mut self,
+        duration: u64,
+        participation_cost: u128,
+        fungible_token: Option<ActorId>,
+    ) -> Result<Event, Error> {
+        self.send(
+            ADMIN,
+            Action::Start {
+                duration,
+                participation_cost,
+                fungible_token,
+                selection: Selection::Uniform,
+                raffle: None,
+                pricing: Pricing::Fixed,
+            },
+            0,
+        )
+    }
+
+    pub fn state(&self) -> State {
+        self.contract.state(&self.env)
+    }
+}
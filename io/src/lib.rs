@@ -1,7 +1,14 @@
 #![no_std]
 
 use gmeta::{InOut, Metadata};
-use gstd::{errors::Error as GstdError, prelude::*, ActorId};
+use gstd::{errors::Error as GstdError, prelude::*};
+use rand::{RngCore, SeedableRng};
+use rand_xoshiro::Xoshiro128PlusPlus;
+
+pub use gstd::ActorId;
+
+#[cfg(feature = "testing")]
+pub mod testing;
 
 pub struct ContractMetadata;
 
@@ -9,7 +16,7 @@ impl Metadata for ContractMetadata {
     type Init = InOut<Initialize, Result<(), Error>>;
     type Handle = InOut<Action, Result<Event, Error>>;
     type Reply = ();
-    type Others = ();
+    type Others = InOut<(), Notification>;
     type Signal = ();
     type State = State;
 }
@@ -21,6 +28,407 @@ impl Metadata for ContractMetadata {
 /// participants.
 pub const MAX_NUMBER_OF_PLAYERS: usize = 2usize.pow(16);
 
+/// The amount of gas reserved on [`Action::Start`] to settle a game round.
+///
+/// [`Action::PickWinner`] sends a prize fund from this reservation, so the
+/// settlement doesn't depend on the gas attached by the game administrator.
+/// A reservation left unused by a previous game round, e.g. a cancelled one, is
+/// reused instead if it outlives the players entry stage by at least a half of
+/// its margin.
+pub const SETTLEMENT_GAS: u64 = 10_000_000_000;
+
+/// The maximum number of gas reservations made by [`Action::Start`] &
+/// [`Action::ReserveGas`] that aren't used or expired yet.
+pub const MAX_NUMBER_OF_RESERVATIONS: usize = 256;
+
+/// The maximum number of players whose [`PlayerStats`] are tracked.
+///
+/// If it's reached, statistics of the least recently active player are
+/// evicted to track a new one.
+pub const MAX_NUMBER_OF_TRACKED_PLAYERS: usize = MAX_NUMBER_OF_PLAYERS;
+
+/// The default number of blocks the contract waits for a reply from an FT
+/// contract until [`Action::ConfigureTokenCalls`] changes it.
+///
+/// Game rounds in fungible tokens require a finite timeout, so an entry whose
+/// transfer is never replied doesn't hold its place forever.
+pub const DEFAULT_TOKEN_REPLY_TIMEOUT: u32 = 100;
+
+/// The maximum number of [`State::subscribers`].
+pub const MAX_NUMBER_OF_SUBSCRIBERS: usize = 32;
+
+/// The denominator of [`Pricing::Exponential`] rates.
+const BASIS_POINTS: u128 = 10_000;
+
+/// The upper bound of [`Decimal`] significands, so a product of two of them
+/// fits in `u128`.
+const MAX_SIGNIFICAND: u128 = 10u128.pow(19);
+
+/// Picks an index of a winner among `number_of_players` players.
+///
+/// It's the pick of [`Selection::Uniform`].
+///
+/// # Panics
+/// If `number_of_players` is 0.
+pub fn winner_index(seed: u64, number_of_players: usize) -> usize {
+    Randomness::new(seed).below(number_of_players as u128) as usize
+}
+
+/// A deterministic source of random numbers for picking winners.
+///
+/// The contract seeds it with a timestamp of a block in which
+/// [`Action::PickWinner`] is processed, so anyone can verify a pick. Results
+/// don't depend on a platform.
+#[derive(Debug, Clone)]
+pub struct Randomness(Xoshiro128PlusPlus);
+
+impl Randomness {
+    pub fn new(seed: u64) -> Self {
+        Self(Xoshiro128PlusPlus::seed_from_u64(seed))
+    }
+
+    /// Returns a uniformly distributed number in `0..bound`.
+    ///
+    /// A random number is drawn again if it falls into the incomplete last
+    /// interval of `bound` numbers (rejection sampling), so the result isn't
+    /// biased towards small numbers as with a plain modulo reduction.
+    ///
+    /// # Panics
+    /// If `bound` is 0.
+    pub fn below(&mut self, bound: u128) -> u128 {
+        assert_ne!(bound, 0, "`bound` must be greater than 0");
+
+        // The size of the incomplete interval is `2^bits mod bound`, so
+        // numbers from `threshold` to the maximum are a multiple of `bound`.
+        if let Ok(bound) = u32::try_from(bound) {
+            let threshold = bound.wrapping_neg() % bound;
+
+            loop {
+                let number = self.0.next_u32();
+
+                if number >= threshold {
+                    break (number % bound).into();
+                }
+            }
+        } else {
+            let threshold = bound.wrapping_neg() % bound;
+
+            loop {
+                let number = u128::from(self.0.next_u64()) << 64 | u128::from(self.0.next_u64());
+
+                if number >= threshold {
+                    break number % bound;
+                }
+            }
+        }
+    }
+}
+
+/// A strategy of picking winners of a game round.
+///
+/// Every strategy draws numbers from [`Randomness`], so anyone can repeat a
+/// pick with [`Selection::pick()`] to verify it.
+#[derive(
+    Debug, Default, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, TypeInfo, Hash,
+)]
+pub enum Selection {
+    /// One winner, every player has the same chance to win.
+    #[default]
+    Uniform,
+    /// One winner, the chance of a player to win is proportional to its
+    /// stake (see [`State::stakes`]).
+    ///
+    /// A stake is a price paid by a player, so stakes only differ if a game
+    /// round is started with a [`Pricing`] other than [`Pricing::Fixed`].
+    /// Otherwise, or if all stakes are 0, it's the same as
+    /// [`Selection::Uniform`].
+    Weighted,
+    /// The given number of different winners, every player has the same
+    /// chance to win.
+    ///
+    /// A prize fund is split equally between winners, and the remainder of
+    /// the division goes to the first one. If there are less players than
+    /// winners, every player wins.
+    ///
+    /// The number of winners must be greater than 0.
+    Multiple(u32),
+}
+
+impl Selection {
+    /// Picks indexes of winners among players with `stakes` in the draw
+    /// order.
+    ///
+    /// Returns an empty [`Vec`] if there are no players.
+    pub fn pick(self, seed: u64, stakes: &[u128]) -> Vec<usize> {
+        if stakes.is_empty() {
+            return vec![];
+        }
+
+        let mut randomness = Randomness::new(seed);
+        let number_of_players = stakes.len();
+
+        match self {
+            Self::Uniform => vec![winner_index(seed, number_of_players)],
+            Self::Weighted => {
+                // Stakes can't realistically overflow `u128`, but if they do,
+                // the last players just get less chances.
+                let total = stakes
+                    .iter()
+                    .fold(0u128, |total, stake| total.saturating_add(*stake));
+
+                if total == 0 {
+                    return vec![winner_index(seed, number_of_players)];
+                }
+
+                let mut number = randomness.below(total);
+                let index = stakes
+                    .iter()
+                    .position(|stake| {
+                        let is_winner = number < *stake;
+
+                        number = number.saturating_sub(*stake);
+
+                        is_winner
+                    })
+                    .unwrap_or(number_of_players - 1);
+
+                vec![index]
+            }
+            Self::Multiple(number_of_winners) => {
+                let number_of_winners = number_of_players.min(number_of_winners as usize);
+                // A partial Fisher-Yates shuffle of player indexes that only
+                // stores swapped indexes.
+                let mut swapped = BTreeMap::new();
+
+                (0..number_of_winners)
+                    .map(|index| {
+                        let other =
+                            index + randomness.below((number_of_players - index) as u128) as usize;
+                        let winner = swapped.get(&other).copied().unwrap_or(other);
+
+                        swapped.insert(other, swapped.get(&index).copied().unwrap_or(index));
+
+                        winner
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// A fixed supply of tickets of a game round.
+///
+/// Every player buys one ticket with [`Action::Enter`], so the number of
+/// tickets limits the number of players.
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, TypeInfo, Hash)]
+pub struct Raffle {
+    /// The number of tickets for sale.
+    ///
+    /// Must be greater than 0 and not greater than [`MAX_NUMBER_OF_PLAYERS`].
+    pub tickets: u32,
+    /// What happens if not all tickets are sold by the end of the players
+    /// entry stage.
+    pub unsold: UnsoldTickets,
+}
+
+/// A policy for unsold tickets of a [`Raffle`].
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, TypeInfo, Hash)]
+pub enum UnsoldTickets {
+    /// Winners are picked among players who bought tickets as in a game round
+    /// without a raffle.
+    Draw,
+    /// No winners are picked, and [`Action::PickWinner`] sends participation
+    /// costs back to players instead.
+    Refund,
+}
+
+/// A pricing of a participation in a game round.
+///
+/// The price of [`Action::Enter`] is calculated by [`Pricing::price()`] at the
+/// time of its execution from a base participation cost set by
+/// [`Action::Start`].
+#[derive(
+    Debug, Default, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, TypeInfo, Hash,
+)]
+pub enum Pricing {
+    /// The price is always the participation cost.
+    #[default]
+    Fixed,
+    /// A linear bonding curve, every entry raises the price by `step`.
+    Linear {
+        /// The price increment per entry.
+        step: u128,
+    },
+    /// An exponential bonding curve, every entry raises the price by `rate`
+    /// basis points (1/10000).
+    ///
+    /// The price is the participation cost multiplied by
+    /// `(1 + rate / 10000) ^ entries` rounded down. The power is raised by
+    /// squaring with 19 significant digits, so the price can be less than the
+    /// exact one by a relative error of less than 10^-13.
+    Exponential {
+        /// The price increment per entry in basis points.
+        rate: u32,
+    },
+    /// The price is `price` until `duration` (in milliseconds) passes since
+    /// the start of a game round, and then it's the participation cost.
+    ///
+    /// `price` mustn't be greater than the participation cost.
+    EarlyBird {
+        /// The early-bird price.
+        price: u128,
+        /// The duration (in milliseconds) of the early-bird period.
+        duration: u64,
+    },
+    /// A Dutch auction, the price linearly decays from the participation cost
+    /// at the start of a game round to `floor` at the end of the players entry
+    /// stage, rounded up.
+    ///
+    /// `floor` mustn't be greater than the participation cost.
+    DutchAuction {
+        /// The price at the end of the players entry stage.
+        floor: u128,
+    },
+}
+
+impl Pricing {
+    /// Calculates the price of the next entry into a game round with the
+    /// given `participation_cost` & `duration` of the players entry stage
+    /// after `entries` players entered it and `elapsed` milliseconds passed
+    /// since its start.
+    ///
+    /// The price saturates at [`u128::MAX`].
+    pub fn price(
+        self,
+        participation_cost: u128,
+        duration: u64,
+        entries: u32,
+        elapsed: u64,
+    ) -> u128 {
+        match self {
+            Self::Fixed => participation_cost,
+            Self::Linear { step } => {
+                participation_cost.saturating_add(step.saturating_mul(entries.into()))
+            }
+            Self::Exponential { rate } => {
+                // `BASIS_POINTS` is 10^4.
+                let mut base = Decimal::new(BASIS_POINTS + u128::from(rate), -4);
+                let mut factor = Decimal::new(1, 0);
+                let mut exponent = entries;
+
+                while exponent != 0 {
+                    if exponent & 1 == 1 {
+                        factor = factor.mul(base);
+                    }
+
+                    exponent >>= 1;
+
+                    if exponent != 0 {
+                        base = base.mul(base);
+                    }
+                }
+
+                factor.scale(participation_cost)
+            }
+            Self::EarlyBird {
+                price,
+                duration: early_bird_duration,
+            } => {
+                if elapsed < early_bird_duration {
+                    price
+                } else {
+                    participation_cost
+                }
+            }
+            Self::DutchAuction { floor } => {
+                if elapsed >= duration {
+                    return floor;
+                }
+
+                let range = participation_cost.saturating_sub(floor);
+                let (duration, elapsed) = (u128::from(duration), u128::from(elapsed));
+                // `range * elapsed / duration` without an overflow.
+                let decay = range / duration * elapsed + range % duration * elapsed / duration;
+
+                participation_cost - decay
+            }
+        }
+    }
+
+    /// Whether the pricing is valid for the given `participation_cost`.
+    pub fn is_valid(self, participation_cost: u128) -> bool {
+        match self {
+            Self::EarlyBird { price, .. } => price <= participation_cost,
+            Self::DutchAuction { floor } => floor <= participation_cost,
+            _ => true,
+        }
+    }
+}
+
+/// A number `significand * 10^exponent`, in which [`Pricing::Exponential`]
+/// raises its growth factor to a power.
+///
+/// Unlike a fixed point number, it doesn't overflow when the factor outgrows
+/// `u128`, while a price still fits in it.
+#[derive(Clone, Copy)]
+struct Decimal {
+    /// Less than [`MAX_SIGNIFICAND`].
+    significand: u128,
+    exponent: i32,
+}
+
+impl Decimal {
+    /// Drops the least significant digits of `significand` beyond
+    /// [`MAX_SIGNIFICAND`], i.e. rounds the number down.
+    fn new(mut significand: u128, mut exponent: i32) -> Self {
+        while significand >= MAX_SIGNIFICAND {
+            significand /= 10;
+            exponent = exponent.saturating_add(1);
+        }
+
+        Self {
+            significand,
+            exponent,
+        }
+    }
+
+    fn mul(self, other: Self) -> Self {
+        // Both significands are less than 10^19, so their product is less than
+        // 10^38 and can't overflow.
+        Self::new(
+            self.significand * other.significand,
+            self.exponent.saturating_add(other.exponent),
+        )
+    }
+
+    /// Multiplies `value` by this number, rounding the result down &
+    /// saturating at [`u128::MAX`].
+    ///
+    /// This number mustn't be less than 1, so the exponent isn't less than
+    /// -18.
+    fn scale(self, value: u128) -> u128 {
+        if value == 0 || self.significand == 0 {
+            return 0;
+        }
+
+        if let Ok(exponent) = u32::try_from(self.exponent) {
+            return 10u128
+                .checked_pow(exponent)
+                .and_then(|power| value.checked_mul(self.significand)?.checked_mul(power))
+                .unwrap_or(u128::MAX);
+        }
+
+        let divisor = 10u128.pow(self.exponent.unsigned_abs());
+
+        // `value * significand / divisor` without an overflow. The remainder
+        // is less than 10^18, so its product with the significand can't
+        // overflow.
+        (value / divisor)
+            .saturating_mul(self.significand)
+            .saturating_add(value % divisor * self.significand / divisor)
+    }
+}
+
 /// Initializes the contract.
 ///
 /// # Requirements
@@ -32,6 +440,11 @@ pub struct Initialize {
     /// [`ActorId`] of the game administrator that'll have the rights to
     /// [`Action::Start`] a game round and [`Action::PickWinner`].
     pub admin: ActorId,
+    /// The existential deposit of the chain.
+    ///
+    /// The native value less than this amount can't be sent, so such payouts
+    /// & refunds are added to [`State::dust`] instead.
+    pub existential_deposit: u128,
 }
 
 /// Sends the contract info about what it should do.
@@ -43,6 +456,13 @@ pub enum Action {
     /// - [`msg::source()`](gstd::msg::source) must be the game administrator.
     /// - The current game round must be over.
     /// - `ft_actor_id` mustn't be [`ActorId::zero()`].
+    /// - `selection` must be valid (see [`Selection`]).
+    /// - `raffle` must be valid (see [`Raffle`]).
+    /// - `pricing` must be valid (see [`Pricing`]).
+    /// - A game round in fungible tokens requires a finite
+    /// [`State::token_reply_timeout`].
+    /// - [`msg::source()`](gstd::msg::source) must attach enough gas to reserve
+    /// [`SETTLEMENT_GAS`] unless an unused reservation is reused.
     ///
     /// On success, replies with [`Event::Started`].
     Start {
@@ -51,7 +471,9 @@ pub enum Action {
         /// After that, no one will be able to enter a game round and a winner
         /// should be picked.
         duration: u64,
-        /// The price of a participation in a game round.
+        /// The base price of a participation in a game round.
+        ///
+        /// Also see `pricing`.
         participation_cost: u128,
         /// A currency (or FT contract [`ActorId`]) of a game round.
         ///
@@ -59,10 +481,20 @@ pub enum Action {
         /// cost will be collected. [`None`] means that the native value will be
         /// used instead of fungible tokens.
         fungible_token: Option<ActorId>,
+        /// A strategy of picking winners of a game round.
+        selection: Selection,
+        /// A fixed supply of tickets of a game round.
+        ///
+        /// When the last ticket is sold, the players entry stage ends
+        /// regardless of `duration`. [`None`] means that the number of
+        /// players is only limited by [`MAX_NUMBER_OF_PLAYERS`].
+        raffle: Option<Raffle>,
+        /// A pricing of a participation in a game round.
+        pricing: Pricing,
     },
 
-    /// Randomly picks a winner from current game round participants (players)
-    /// and sends a prize fund to it.
+    /// Randomly picks winners from current game round participants (players)
+    /// with [`State::selection`] and sends a prize fund to them.
     ///
     /// The randomness of a winner pick depends on
     /// [`exec::block_timestamp()`](gstd::exec::block_timestamp).
@@ -70,43 +502,175 @@ pub enum Action {
     /// exactly predict a winner if the time of an execution of this action is
     /// unknown.
     ///
-    /// If no one participated in the round, then a winner will be
-    /// [`ActorId::zero()`].
+    /// If no one participated in the round, then it's cancelled. If the round
+    /// is a [`Raffle`] with unsold tickets and [`UnsoldTickets::Refund`], then
+    /// participation costs are sent back to players, and it's cancelled too.
+    /// Refunds of the native value less than the existential deposit are added
+    /// to [`State::dust`].
+    ///
+    /// If the current game round currency is the native value, [`State::dust`]
+    /// is added to a prize fund. Prizes less than the existential deposit
+    /// aren't sent and become [`State::dust`] instead, while other winners are
+    /// still paid.
+    ///
+    /// Contains the expected game round (see [`State::round`]). [`None`] means
+    /// that the current game round is expected.
     ///
     /// # Requirements
+    /// - The expected game round must be the current one.
     /// - [`msg::source()`](gstd::msg::source) must be the game administrator.
-    /// - The players entry stage must be over.
+    /// - The players entry stage must be over, or all tickets of a [`Raffle`]
+    /// must be sold.
     /// - A winner mustn't already be picked.
+    /// - Transfers of [`Action::Enter`] mustn't be awaited from the FT
+    /// contract, unless they're overdue by [`State::token_reply_timeout`] at
+    /// the time they were sent.
     ///
-    /// On success, replies with [`Event::Winner`].
-    PickWinner,
+    /// On success, replies with [`Event::Winner`], or [`Event::Winners`] if
+    /// the game round has [`Selection::Multiple`], or [`Event::Cancelled`] if
+    /// no one participated in the round, or [`Event::Refunded`] if unsold
+    /// tickets were refunded.
+    PickWinner(Option<u64>),
 
     /// Pays a participation cost and adds [`msg::source()`] to the current game
     /// round participants (players).
     ///
-    /// A participation cost and its currency can be queried from the contract
-    /// state.
+    /// The price of a participation is calculated by [`State::pricing`] at the
+    /// time of an execution of this action. It & its currency can be queried
+    /// from the contract state (see [`State::price`]).
+    ///
+    /// Contains the expected game round (see [`State::round`]), so a delayed
+    /// action can't enter a next game round with another participation cost.
+    /// [`None`] means that the current game round is expected.
     ///
     /// # Requirements
+    /// - The expected game round must be the current one.
     /// - The players entry stage mustn't be over.
+    /// - If the current game round is a [`Raffle`], its tickets mustn't be
+    /// sold out.
     /// - [`msg::source()`] mustn't already participate.
-    /// - [`msg::source()`] must have enough currency to pay a participation
-    /// cost.
+    /// - [`msg::source()`] must have enough currency to pay the price.
     /// - If the current game round currency is the native value
     /// (`fungible_token` is [`None`]), [`msg::source()`] must send this action
-    /// with the amount of the value exactly equal to a participation cost.
+    /// with the amount of the value not less than the price. Otherwise, the
+    /// value is sent back, or added to [`State::dust`] if it's less than the
+    /// existential deposit.
+    ///
+    /// The price can change between sending & executing this action, e.g. it
+    /// drops in a [`Pricing::DutchAuction`] or grows with entries of other
+    /// players on a bonding curve, so the value exceeding the price is sent
+    /// back on success in the same way.
+    ///
+    /// If the current game round currency is a fungible token, a place of
+    /// [`msg::source()`] is held while its transfer is awaited, so concurrent
+    /// entries can't oversell tickets or pay the same price, and the round
+    /// can't be settled. The place is released if the transfer fails, and the
+    /// player is added if it succeeds, even after the end of the players entry
+    /// stage.
     ///
     /// On success, replies with [`Event::PlayerAdded`].
     ///
     /// [`msg::source()`]: gstd::msg::source
-    Enter,
+    Enter(Option<u64>),
+
+    /// Sends the native value that isn't owed to anyone to `to`.
+    ///
+    /// The program can accumulate such value from direct transfers. Only the
+    /// surplus beyond liabilities of the current game round can be recovered,
+    /// i.e. [`exec::value_available()`](gstd::exec::value_available) minus the
+    /// prize fund of an active game round in the native value and
+    /// [`State::dust`].
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be the game administrator.
+    /// - `to` mustn't be [`ActorId::zero()`].
+    /// - `amount` mustn't exceed the surplus.
+    /// - `amount` mustn't be less than the existential deposit.
+    ///
+    /// On success, replies with [`Event::ValueRecovered`].
+    RecoverValue {
+        /// A recipient of the recovered value.
+        to: ActorId,
+        /// The amount of the value to recover.
+        amount: u128,
+    },
+
+    /// Tops up the gas reservation used to settle game rounds.
+    ///
+    /// Each settlement consumes one reservation, so this action should be
+    /// used if a game round outlives its reservation made on
+    /// [`Action::Start`], or if a previous settlement attempt failed.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be the game administrator.
+    /// - [`msg::source()`](gstd::msg::source) must attach enough gas to
+    /// reserve `amount`.
+    ///
+    /// On success, replies with [`Event::GasReserved`].
+    ReserveGas {
+        /// The amount of gas to reserve.
+        amount: u64,
+        /// The number of blocks the reservation will be valid for.
+        duration: u32,
+    },
+
+    /// Queries the gas reservation used to settle game rounds.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be the game administrator.
+    ///
+    /// On success, replies with [`Event::GasReservation`].
+    GasReservation,
+
+    /// Configures calls to FT contracts.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be the game administrator.
+    /// - `reply_timeout` must be finite while the current game round in
+    /// fungible tokens isn't over.
+    ///
+    /// On success, replies with [`Event::TokenCallsConfigured`].
+    ConfigureTokenCalls {
+        /// The amount of gas reserved for handling a reply from a FT contract.
+        reply_deposit: u64,
+        /// The number of blocks the contract waits for a reply from a FT
+        /// contract.
+        ///
+        /// If a reply doesn't arrive in time, an action fails with
+        /// [`Error::TokenTimeout`]. [`None`] or zero means no timeout. The
+        /// default is [`DEFAULT_TOKEN_REPLY_TIMEOUT`].
+        reply_timeout: Option<u32>,
+    },
+
+    /// Adds a subscriber that'll receive a [`Notification`] about every
+    /// processed action that changes the contract state.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be the game administrator.
+    /// - A subscriber mustn't be [`ActorId::zero()`].
+    /// - A subscriber mustn't already be subscribed.
+    /// - The number of subscribers mustn't exceed [`MAX_NUMBER_OF_SUBSCRIBERS`].
+    ///
+    /// On success, replies with [`Event::Subscribed`].
+    Subscribe(ActorId),
+
+    /// Removes a subscriber added by [`Action::Subscribe`].
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be the game administrator.
+    /// - A subscriber must be subscribed.
+    ///
+    /// On success, replies with [`Event::Unsubscribed`].
+    Unsubscribe(ActorId),
 }
 
 /// A result of processed [`Action`].
-#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, TypeInfo, Hash)]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
 pub enum Event {
     /// Should be returned from [`Action::Start`].
     Started {
+        /// See [`State::round`].
+        round: u64,
         /// The end time (in milliseconds) of the players entry stage.
         ///
         /// After that, the game administrator can pick a winner.
@@ -115,11 +679,155 @@ pub enum Event {
         participation_cost: u128,
         /// See [`Action::Start`].
         fungible_token: Option<ActorId>,
+        /// See [`Action::Start`].
+        selection: Selection,
+        /// See [`Action::Start`].
+        raffle: Option<Raffle>,
+        /// See [`Action::Start`].
+        pricing: Pricing,
     },
     /// Should be returned from [`Action::PickWinner`].
-    Winner(ActorId),
+    Winner {
+        /// See [`State::round`].
+        round: u64,
+        /// The winner of the game round.
+        winner: ActorId,
+        /// The amount of currency sent to `winner`.
+        prize: u128,
+        /// The amount of the native value that wasn't sent because it's less
+        /// than the existential deposit.
+        ///
+        /// Also see [`State::dust`].
+        dust: u128,
+    },
+    /// Should be returned from [`Action::PickWinner`] if the game round has
+    /// [`Selection::Multiple`].
+    Winners {
+        /// See [`State::round`].
+        round: u64,
+        /// Winners of the game round in the draw order with amounts of
+        /// currency sent to them.
+        ///
+        /// A prize less than the existential deposit isn't sent, so its
+        /// amount is 0, and it's added to `dust` instead.
+        winners: Vec<(ActorId, u128)>,
+        /// See [`Event::Winner`].
+        dust: u128,
+    },
+    /// Should be returned from [`Action::PickWinner`] if no one participated
+    /// in the game round.
+    Cancelled {
+        /// See [`State::round`].
+        round: u64,
+    },
     /// Should be returned from [`Action::Enter`].
-    PlayerAdded(ActorId),
+    PlayerAdded {
+        /// See [`State::round`].
+        round: u64,
+        /// [`msg::source()`](gstd::msg::source) of [`Action::Enter`].
+        player: ActorId,
+    },
+    /// Should be returned from [`Action::RecoverValue`].
+    ValueRecovered {
+        /// See [`Action::RecoverValue`].
+        to: ActorId,
+        /// See [`Action::RecoverValue`].
+        amount: u128,
+    },
+    /// Should be returned from [`Action::ReserveGas`].
+    GasReserved {
+        /// See [`Action::ReserveGas`].
+        amount: u64,
+        /// The block height at which the reservation expires.
+        expiration: u32,
+    },
+    /// Should be returned from [`Action::GasReservation`].
+    GasReservation {
+        /// See [`State::reserved_gas`].
+        reserved_gas: u64,
+        /// The block height at which the soonest reservation expires.
+        ///
+        /// [`None`] means that there's no reservation.
+        expiration: Option<u32>,
+    },
+    /// Should be returned from [`Action::ConfigureTokenCalls`].
+    TokenCallsConfigured {
+        /// See [`Action::ConfigureTokenCalls`].
+        reply_deposit: u64,
+        /// See [`Action::ConfigureTokenCalls`].
+        reply_timeout: Option<u32>,
+    },
+    /// Should be returned from [`Action::Subscribe`].
+    Subscribed(ActorId),
+    /// Should be returned from [`Action::Unsubscribe`].
+    Unsubscribed(ActorId),
+    /// Should be returned from [`Action::PickWinner`] if participation costs
+    /// were sent back to players because of [`UnsoldTickets::Refund`].
+    Refunded {
+        /// See [`State::round`].
+        round: u64,
+        /// The total amount of currency sent back to players.
+        amount: u128,
+        /// See [`Event::Winner`].
+        dust: u128,
+    },
+}
+
+/// Lifetime statistics of a player.
+///
+/// Also see [`MAX_NUMBER_OF_TRACKED_PLAYERS`].
+#[derive(Debug, Default, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
+pub struct PlayerStats {
+    /// The number of entered game rounds.
+    pub rounds_entered: u64,
+    /// The number of won game rounds.
+    pub wins: u64,
+    /// The last game round the player entered or won.
+    pub last_round: u64,
+    /// Statistics per currency (or FT contract [`ActorId`]).
+    ///
+    /// [`None`] means the native value.
+    pub currencies: BTreeMap<Option<ActorId>, CurrencyStats>,
+}
+
+/// Players & their winnings in one currency.
+///
+/// Also see [`State::leaderboards`].
+pub type Leaderboard = Vec<(ActorId, u128)>;
+
+/// Statistics of a player in one currency.
+///
+/// Also see [`PlayerStats`].
+#[derive(
+    Debug, Default, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, TypeInfo, Hash,
+)]
+pub struct CurrencyStats {
+    /// The amount of currency spent on participation costs.
+    pub spent: u128,
+    /// The amount of currency won.
+    pub won: u128,
+}
+
+/// A notification about a processed [`Action`] that changed the contract
+/// state.
+///
+/// It's sent to [`State::subscribers`] and to [`ActorId::zero()`], so it also
+/// appears in the user-visible event stream.
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo, Hash)]
+pub struct Notification {
+    /// The number of the game round during which the action was processed.
+    ///
+    /// Also see [`State::round`].
+    pub round: u64,
+    /// The sequence number of the notification.
+    ///
+    /// It's incremented with every notification, so a gap means that a
+    /// notification was missed.
+    pub sequence: u64,
+    /// The time (in milliseconds) the action was processed at.
+    pub timestamp: u64,
+    /// A result of the action.
+    pub event: Event,
 }
 
 /// Contract execution error variants.
@@ -127,12 +835,40 @@ pub enum Event {
 pub enum Error {
     /// [`msg::source()`](gstd::msg::source) isn't the administrator.
     AccessRestricted,
-    /// The current game round wasn't in an expected status.
-    ///
-    /// E.g. the game administrator can't pick a winner if the player entry
-    /// stage isn't over, or an user can't entry a game round if the entry
-    /// stage is over.
-    UnexpectedGameStatus,
+    /// [`Action::Start`] was sent while the current game round is active.
+    RoundIsActive {
+        /// See [`State::status`].
+        status: RoundStatus,
+        /// See [`Event::Started`].
+        ending: u64,
+    },
+    /// [`Action::PickWinner`] was sent before the end of the players entry
+    /// stage.
+    EntryStageNotOver {
+        /// See [`Event::Started`].
+        ending: u64,
+        /// The time (in milliseconds) the action was processed at.
+        now: u64,
+    },
+    /// [`Action::PickWinner`] was sent after a winner of the current game
+    /// round was picked.
+    WinnerAlreadyPicked {
+        /// See [`State::winner`].
+        winner: ActorId,
+    },
+    /// [`Action::PickWinner`] was sent after the current game round was
+    /// cancelled.
+    RoundIsCancelled,
+    /// [`Action::Enter`] was sent after the end of the players entry stage.
+    EntryStageOver {
+        /// See [`Event::Started`].
+        ending: u64,
+        /// The time (in milliseconds) the action was processed at.
+        now: u64,
+    },
+    /// [`Action::Enter`] or [`Action::PickWinner`] was sent before the first
+    /// game round was started.
+    NoRoundStarted,
     /// [`ActorId::zero()`] was found where it's forbidden.
     ZeroActorId,
     /// The current FT contract failed to complete a transfer transaction.
@@ -141,22 +877,128 @@ pub enum Error {
     /// contract or didn't have enough tokens for participating.
     TokenTransferFailed,
     /// The contract reached a limit of protection against the memory overflow.
-    MemoryLimitExceeded,
+    MemoryLimitExceeded {
+        /// The limit that was reached.
+        limit: Limit,
+        /// The maximum number of the limited entities.
+        maximum: u32,
+    },
     /// [`msg::source()`](gstd::msg::source) is already participating in the
     /// current game round.
     AlreadyParticipating,
-    /// [`msg::source()`] sent [`Action::Enter`] with an incorrent amount of the
-    /// native value.
+    /// [`msg::source()`] sent [`Action::Enter`] with the amount of the native
+    /// value less than the price.
     ///
     /// [`msg::source()`] should set the value manually because the current game
     /// round is going without a FT contract (also see [`Action::Enter`]).
     ///
     /// [`msg::source()`]: gstd::msg::source
-    InvalidParticipationCost,
+    InvalidParticipationCost {
+        /// The price at the time of an execution of [`Action::Enter`].
+        ///
+        /// Also see [`Pricing`].
+        expected: u128,
+        /// The amount of the value sent with [`Action::Enter`].
+        sent: u128,
+    },
+    /// [`Action::RecoverValue`] requested more than the surplus of the native
+    /// value.
+    InsufficientSurplus {
+        /// The amount of the native value that can be recovered.
+        surplus: u128,
+    },
+    /// The native value to send is less than the existential deposit.
+    BelowExistentialDeposit {
+        /// See [`Initialize`].
+        existential_deposit: u128,
+    },
+    /// A subscriber is already subscribed.
+    AlreadySubscribed,
+    /// A subscriber isn't subscribed.
+    NotSubscribed,
+    /// [`Action::Enter`] or [`Action::PickWinner`] expected another game
+    /// round.
+    UnexpectedRound {
+        /// The game round expected by an action.
+        expected: u64,
+        /// See [`State::round`].
+        current: u64,
+    },
+    /// [`Action::Start`] was sent with an invalid [`Selection`].
+    InvalidSelection,
+    /// The current FT contract didn't reply in time.
+    ///
+    /// A transfer transaction is kept, so sending the same action again will
+    /// retry it with the same transaction ID. Also see
+    /// [`Action::ConfigureTokenCalls`].
+    TokenTimeout,
+    /// [`Action::Start`] was sent with an invalid [`Raffle`].
+    InvalidRaffle,
+    /// [`Action::Enter`] was sent after all tickets of the current [`Raffle`]
+    /// were sold.
+    SoldOut,
+    /// [`Action::Start`] was sent with an invalid [`Pricing`].
+    InvalidPricing,
+    /// The current FT contract replied to a transfer transaction with neither
+    /// a success nor a failure.
+    ///
+    /// Like after [`Error::TokenTransferFailed`], the transaction isn't kept,
+    /// so sending the same action again will make a new one.
+    UnexpectedTokenReply,
+    /// [`Action::PickWinner`] was sent while transfers of [`Action::Enter`]
+    /// are awaited from the FT contract.
+    ///
+    /// The action can be sent again after they're replied or timed out.
+    EntriesPending,
+    /// [`Action::Start`] of a game round in fungible tokens was sent while
+    /// [`State::token_reply_timeout`] is infinite, or
+    /// [`Action::ConfigureTokenCalls`] tried to make it infinite during such a
+    /// round.
+    InfiniteTokenReplyTimeout,
     /// See [`GstdError`].
     ContractError(String),
 }
 
+/// A lifecycle status of a game round.
+#[derive(
+    Debug, Default, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, TypeInfo, Hash,
+)]
+pub enum RoundStatus {
+    /// No game round was started yet.
+    #[default]
+    NotStarted,
+    /// The players entry stage, players can [`Action::Enter`] a game round.
+    Entry,
+    /// The players entry stage is over, or all tickets of a [`Raffle`] are
+    /// sold, so the game administrator can [`Action::PickWinner`].
+    AwaitingDraw,
+    /// A winner is picked, but a prize fund isn't sent yet, or unsold tickets
+    /// aren't refunded yet.
+    ///
+    /// It happens if a settlement failed, e.g. the FT contract failed to
+    /// complete a transfer transaction. [`Action::PickWinner`] should be sent
+    /// again to retry it.
+    Settling,
+    /// A winner is picked and a prize fund is sent to it.
+    Finished,
+    /// No one participated in a game round, or unsold tickets were refunded,
+    /// so there's no winner.
+    Cancelled,
+}
+
+/// A limit of protection against the memory overflow.
+///
+/// Also see [`Error::MemoryLimitExceeded`].
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, TypeInfo, Hash)]
+pub enum Limit {
+    /// See [`MAX_NUMBER_OF_PLAYERS`].
+    Players,
+    /// See [`MAX_NUMBER_OF_RESERVATIONS`].
+    Reservations,
+    /// See [`MAX_NUMBER_OF_SUBSCRIBERS`].
+    Subscribers,
+}
+
 impl From<GstdError> for Error {
     fn from(error: GstdError) -> Self {
         Self::ContractError(error.to_string())
@@ -175,19 +1017,76 @@ pub struct State {
     pub ending: u64,
     /// Participants of the current game round.
     pub players: Vec<ActorId>,
+    /// Amounts of currency paid by `players` to enter the current game round
+    /// in the same order.
+    ///
+    /// Also see [`Selection::Weighted`].
+    pub stakes: Vec<u128>,
     /// The current game round prize fund.
     ///
-    /// It's calculated by multiplying `participation_cost` and the number of
-    /// `players`.
+    /// It's the sum of `stakes`.
     pub prize_fund: u128,
     /// See [`Action::Start`].
     pub participation_cost: u128,
     /// The winner of the current game round.
-    pub winner: ActorId,
+    ///
+    /// [`None`] means that a winner isn't picked yet, or the current game
+    /// round was cancelled. If the game round has several winners, it's the
+    /// first one.
+    pub winner: Option<ActorId>,
+    /// Winners of the current game round in the draw order.
+    pub winners: Vec<ActorId>,
+    /// See [`Action::Start`].
+    pub selection: Selection,
+    /// See [`Action::Start`].
+    pub raffle: Option<Raffle>,
+    /// See [`Action::Start`].
+    pub pricing: Pricing,
+    /// The price of [`Action::Enter`] into the current game round at the time
+    /// of a state query.
+    ///
+    /// It's only meaningful during the players entry stage. Also see
+    /// [`Pricing`].
+    pub price: u128,
     /// A currency (or a FT contract [`ActorId`]) of the current game round.
     ///
     /// Also see [`Action::Start`].
     pub fungible_token: Option<ActorId>,
-    /// Shows if the current game round is active.
-    pub is_active: bool,
+    /// The status of the current game round.
+    pub status: RoundStatus,
+    /// The amount of gas reserved to settle game rounds.
+    ///
+    /// Also see [`SETTLEMENT_GAS`] & [`Action::ReserveGas`].
+    pub reserved_gas: u64,
+    /// See [`Action::ConfigureTokenCalls`].
+    pub token_reply_deposit: u64,
+    /// See [`Action::ConfigureTokenCalls`].
+    pub token_reply_timeout: Option<u32>,
+    /// See [`Initialize`].
+    pub existential_deposit: u128,
+    /// The native value that couldn't be sent because it's less than the
+    /// existential deposit.
+    ///
+    /// It's carried over to the prize fund of a next game round in the native
+    /// value.
+    pub dust: u128,
+    /// See [`Action::Subscribe`].
+    pub subscribers: Vec<ActorId>,
+    /// The number of the current game round.
+    ///
+    /// It's incremented on every [`Action::Start`], so game rounds are
+    /// numbered from 1, and 0 means that no game round was started yet.
+    pub round: u64,
+    /// Lifetime statistics of tracked players in ascending order of their
+    /// [`ActorId`]s.
+    ///
+    /// Also see [`MAX_NUMBER_OF_TRACKED_PLAYERS`].
+    pub player_stats: Vec<(ActorId, PlayerStats)>,
+    /// Tracked players with winnings per currency (or FT contract
+    /// [`ActorId`]).
+    ///
+    /// [`None`] means the native value. Players are in descending order of
+    /// winnings. Players with equal winnings are in
+    /// ascending order of their [`ActorId`]s.
+    pub leaderboards: Vec<(Option<ActorId>, Leaderboard)>,
 }
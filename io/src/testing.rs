@@ -0,0 +1,49 @@
+//! Test hooks of the contract.
+//!
+//! Only available with the `testing` feature, which must never be enabled in
+//! production builds. The hooks aren't a part of [`Action`](crate::Action) &
+//! [`Event`](crate::Event), so the feature doesn't change the ABI described by
+//! [`ContractMetadata`](crate::ContractMetadata).
+
+use gstd::prelude::*;
+
+/// Randomness of picking winners set by [`TestAction::OverrideRandomness`].
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, TypeInfo, Hash)]
+pub enum RandomnessOverride {
+    /// Winners are picked by [`Selection::pick()`](crate::Selection::pick)
+    /// with this seed instead of the block timestamp.
+    Seed(u64),
+    /// The player with this index wins.
+    ///
+    /// A selection strategy must pick one winner, and the index must be less
+    /// than the number of players. Otherwise,
+    /// [`Action::PickWinner`](crate::Action::PickWinner) fails with
+    /// [`Error::InvalidSelection`](crate::Error::InvalidSelection).
+    Winner(u32),
+}
+
+/// A test hook sent to the contract instead of an [`Action`](crate::Action).
+///
+/// Variants are encoded with indexes that no action uses, so the contract
+/// tells test hooks apart from actions.
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, TypeInfo, Hash)]
+pub enum TestAction {
+    /// Overrides the randomness of picking winners until it's reset with
+    /// [`None`].
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be the game administrator.
+    ///
+    /// On success, replies with [`TestEvent::RandomnessOverridden`].
+    #[codec(index = 255)]
+    OverrideRandomness(Option<RandomnessOverride>),
+}
+
+/// A result of processed [`TestAction`].
+///
+/// The contract replies with `Result<TestEvent, Error>`.
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, TypeInfo, Hash)]
+pub enum TestEvent {
+    /// Should be returned from [`TestAction::OverrideRandomness`].
+    RandomnessOverridden(Option<RandomnessOverride>),
+}
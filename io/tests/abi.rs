@@ -0,0 +1,577 @@
+//! ABI compatibility tests.
+//!
+//! [`ContractMetadata::repr()`] & SCALE encodings of representative values are
+//! compared with snapshots in `tests/snapshots`, so any change of types that
+//! frontends decode fails with a diff naming the affected type. If a change is
+//! deliberate, update snapshots by running these tests with
+//! `GOC_UPDATE_SNAPSHOTS=1`, and mention the change in the changelog.
+
+use game_of_chance_io::*;
+use gmeta::{Metadata, TypesRepr};
+use gstd::{prelude::*, ActorId};
+use scale_info::{form::PortableForm, Field, PortableRegistry, TypeDef, TypeDefPrimitive, Variant};
+use std::{env, fs};
+
+const METADATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/snapshots/metadata.txt");
+const ENCODINGS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/snapshots/encodings.txt");
+
+/// Named sections of a snapshot.
+type Snapshot = BTreeMap<String, String>;
+
+/// An entry of [`gmeta::MetadataRepr`].
+trait Entry {
+    fn render(&self, registry: &PortableRegistry) -> String;
+}
+
+impl Entry for Option<u32> {
+    fn render(&self, registry: &PortableRegistry) -> String {
+        self.map_or_else(|| "()".into(), |id| name(registry, id))
+    }
+}
+
+impl Entry for TypesRepr {
+    fn render(&self, registry: &PortableRegistry) -> String {
+        format!(
+            "{} -> {}",
+            self.input.render(registry),
+            self.output.render(registry)
+        )
+    }
+}
+
+/// A name of a type by which it's referred to in other types.
+fn name(registry: &PortableRegistry, id: u32) -> String {
+    let ty = registry.resolve(id).expect("the registry is incomplete");
+    let names = |ids: Vec<u32>| {
+        ids.into_iter()
+            .map(|id| name(registry, id))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    if let Some(ident) = ty.path.segments.last() {
+        let params = names(
+            ty.type_params
+                .iter()
+                .filter_map(|param| param.ty.as_ref().map(|ty| ty.id))
+                .collect(),
+        );
+
+        return if params.is_empty() {
+            ident.to_string()
+        } else {
+            format!("{ident}<{params}>")
+        };
+    }
+
+    match &ty.type_def {
+        TypeDef::Primitive(primitive) => primitive_name(primitive).into(),
+        TypeDef::Sequence(sequence) => format!("Vec<{}>", name(registry, sequence.type_param.id)),
+        TypeDef::Array(array) => {
+            format!("[{}; {}]", name(registry, array.type_param.id), array.len)
+        }
+        TypeDef::Tuple(tuple) => {
+            format!("({})", names(tuple.fields.iter().map(|ty| ty.id).collect()))
+        }
+        TypeDef::Compact(compact) => format!("Compact<{}>", name(registry, compact.type_param.id)),
+        TypeDef::BitSequence(_) => "BitVec".into(),
+        TypeDef::Composite(_) | TypeDef::Variant(_) => format!("<unnamed #{id}>"),
+    }
+}
+
+fn primitive_name(primitive: &TypeDefPrimitive) -> &'static str {
+    match primitive {
+        TypeDefPrimitive::Bool => "bool",
+        TypeDefPrimitive::Char => "char",
+        TypeDefPrimitive::Str => "str",
+        TypeDefPrimitive::U8 => "u8",
+        TypeDefPrimitive::U16 => "u16",
+        TypeDefPrimitive::U32 => "u32",
+        TypeDefPrimitive::U64 => "u64",
+        TypeDefPrimitive::U128 => "u128",
+        TypeDefPrimitive::U256 => "u256",
+        TypeDefPrimitive::I8 => "i8",
+        TypeDefPrimitive::I16 => "i16",
+        TypeDefPrimitive::I32 => "i32",
+        TypeDefPrimitive::I64 => "i64",
+        TypeDefPrimitive::I128 => "i128",
+        TypeDefPrimitive::I256 => "i256",
+    }
+}
+
+fn fields(registry: &PortableRegistry, fields: &[Field<PortableForm>]) -> String {
+    let rendered = fields
+        .iter()
+        .map(|field| {
+            let ty = name(registry, field.ty.id);
+
+            field
+                .name
+                .as_ref()
+                .map_or_else(|| ty.clone(), |name| format!("{name}: {ty}"))
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    match fields.first() {
+        None => String::new(),
+        Some(field) if field.name.is_some() => format!(" {{ {rendered} }}"),
+        Some(_) => format!("({rendered})"),
+    }
+}
+
+fn variant(registry: &PortableRegistry, variant: &Variant<PortableForm>) -> String {
+    format!(
+        "    {}{} = {}",
+        variant.name,
+        fields(registry, &variant.fields),
+        variant.index
+    )
+}
+
+/// Renders definitions of all named types without docs, so only changes of
+/// the ABI are caught.
+fn metadata_snapshot() -> Snapshot {
+    let repr = ContractMetadata::repr();
+    let registry = PortableRegistry::decode(&mut repr.registry.as_slice())
+        .expect("failed to decode the registry");
+    let mut snapshot = Snapshot::new();
+
+    snapshot.insert(
+        "ContractMetadata".into(),
+        [
+            format!("init: {}", repr.init.render(&registry)),
+            format!("handle: {}", repr.handle.render(&registry)),
+            format!("reply: {}", repr.reply.render(&registry)),
+            format!("others: {}", repr.others.render(&registry)),
+            format!("signal: {}", repr.signal.render(&registry)),
+            format!("state: {}", repr.state.render(&registry)),
+        ]
+        .join("\n"),
+    );
+
+    for ty in &registry.types {
+        if ty.ty.path.segments.is_empty() {
+            continue;
+        }
+
+        let definition = match &ty.ty.type_def {
+            TypeDef::Composite(composite) => {
+                format!("struct{}", fields(&registry, &composite.fields))
+            }
+            TypeDef::Variant(enumeration) => iter::once("enum".into())
+                .chain(
+                    enumeration
+                        .variants
+                        .iter()
+                        .map(|variant| self::variant(&registry, variant)),
+                )
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => name(&registry, ty.id),
+        };
+
+        snapshot.insert(name(&registry, ty.id), definition);
+    }
+
+    snapshot
+}
+
+/// Representative values of every variant of types in [`ContractMetadata`].
+fn encodings_snapshot() -> Snapshot {
+    let actor = ActorId::from(1);
+    let token = Some(ActorId::from(2));
+    let stats = PlayerStats {
+        rounds_entered: 3,
+        wins: 1,
+        last_round: 5,
+        currencies: [(token, CurrencyStats { spent: 30, won: 25 })].into(),
+    };
+    let values = [
+        (
+            "Initialize",
+            Initialize {
+                admin: actor,
+                existential_deposit: 500,
+            }
+            .encode(),
+        ),
+        (
+            "Action::Start",
+            Action::Start {
+                duration: 60_000,
+                participation_cost: 10_000,
+                fungible_token: token,
+                selection: Selection::Multiple(3),
+                raffle: Some(Raffle {
+                    tickets: 100,
+                    unsold: UnsoldTickets::Refund,
+                }),
+                pricing: Pricing::DutchAuction { floor: 5000 },
+            }
+            .encode(),
+        ),
+        ("Action::PickWinner", Action::PickWinner(Some(1)).encode()),
+        ("Action::Enter", Action::Enter(None).encode()),
+        (
+            "Action::RecoverValue",
+            Action::RecoverValue {
+                to: actor,
+                amount: 1000,
+            }
+            .encode(),
+        ),
+        (
+            "Action::ReserveGas",
+            Action::ReserveGas {
+                amount: 10_000_000_000,
+                duration: 14_400,
+            }
+            .encode(),
+        ),
+        ("Action::GasReservation", Action::GasReservation.encode()),
+        (
+            "Action::ConfigureTokenCalls",
+            Action::ConfigureTokenCalls {
+                reply_deposit: 5_000_000_000,
+                reply_timeout: Some(10),
+            }
+            .encode(),
+        ),
+        ("Action::Subscribe", Action::Subscribe(actor).encode()),
+        ("Action::Unsubscribe", Action::Unsubscribe(actor).encode()),
+        (
+            "Event::Started",
+            Event::Started {
+                round: 1,
+                ending: 1_686_000_060_000,
+                participation_cost: 10_000,
+                fungible_token: None,
+                selection: Selection::Weighted,
+                raffle: None,
+                pricing: Pricing::Linear { step: 1000 },
+            }
+            .encode(),
+        ),
+        (
+            "Event::Winner",
+            Event::Winner {
+                round: 1,
+                winner: actor,
+                prize: 30_000,
+                dust: 0,
+            }
+            .encode(),
+        ),
+        (
+            "Event::Winners",
+            Event::Winners {
+                round: 1,
+                winners: vec![(actor, 15_000), (ActorId::from(3), 15_000)],
+                dust: 1,
+            }
+            .encode(),
+        ),
+        ("Event::Cancelled", Event::Cancelled { round: 1 }.encode()),
+        (
+            "Event::Refunded",
+            Event::Refunded {
+                round: 1,
+                amount: 20_000,
+                dust: 1,
+            }
+            .encode(),
+        ),
+        (
+            "Event::PlayerAdded",
+            Event::PlayerAdded {
+                round: 1,
+                player: actor,
+            }
+            .encode(),
+        ),
+        (
+            "Event::ValueRecovered",
+            Event::ValueRecovered {
+                to: actor,
+                amount: 1000,
+            }
+            .encode(),
+        ),
+        (
+            "Event::GasReserved",
+            Event::GasReserved {
+                amount: 10_000_000_000,
+                expiration: 15_000,
+            }
+            .encode(),
+        ),
+        (
+            "Event::GasReservation",
+            Event::GasReservation {
+                reserved_gas: 10_000_000_000,
+                expiration: Some(15_000),
+            }
+            .encode(),
+        ),
+        (
+            "Event::TokenCallsConfigured",
+            Event::TokenCallsConfigured {
+                reply_deposit: 5_000_000_000,
+                reply_timeout: None,
+            }
+            .encode(),
+        ),
+        ("Event::Subscribed", Event::Subscribed(actor).encode()),
+        ("Event::Unsubscribed", Event::Unsubscribed(actor).encode()),
+        ("Error::AccessRestricted", Error::AccessRestricted.encode()),
+        (
+            "Error::RoundIsActive",
+            Error::RoundIsActive {
+                status: RoundStatus::Settling,
+                ending: 1_686_000_060_000,
+            }
+            .encode(),
+        ),
+        (
+            "Error::EntryStageNotOver",
+            Error::EntryStageNotOver {
+                ending: 1_686_000_060_000,
+                now: 1_686_000_000_000,
+            }
+            .encode(),
+        ),
+        (
+            "Error::WinnerAlreadyPicked",
+            Error::WinnerAlreadyPicked { winner: actor }.encode(),
+        ),
+        ("Error::RoundIsCancelled", Error::RoundIsCancelled.encode()),
+        (
+            "Error::EntryStageOver",
+            Error::EntryStageOver {
+                ending: 1_686_000_060_000,
+                now: 1_686_000_120_000,
+            }
+            .encode(),
+        ),
+        ("Error::NoRoundStarted", Error::NoRoundStarted.encode()),
+        ("Error::ZeroActorId", Error::ZeroActorId.encode()),
+        (
+            "Error::TokenTransferFailed",
+            Error::TokenTransferFailed.encode(),
+        ),
+        (
+            "Error::MemoryLimitExceeded",
+            Error::MemoryLimitExceeded {
+                limit: Limit::Subscribers,
+                maximum: MAX_NUMBER_OF_SUBSCRIBERS as u32,
+            }
+            .encode(),
+        ),
+        (
+            "Error::AlreadyParticipating",
+            Error::AlreadyParticipating.encode(),
+        ),
+        (
+            "Error::InvalidParticipationCost",
+            Error::InvalidParticipationCost {
+                expected: 10_000,
+                sent: 9_999,
+            }
+            .encode(),
+        ),
+        (
+            "Error::InsufficientSurplus",
+            Error::InsufficientSurplus { surplus: 100 }.encode(),
+        ),
+        (
+            "Error::BelowExistentialDeposit",
+            Error::BelowExistentialDeposit {
+                existential_deposit: 500,
+            }
+            .encode(),
+        ),
+        (
+            "Error::AlreadySubscribed",
+            Error::AlreadySubscribed.encode(),
+        ),
+        ("Error::NotSubscribed", Error::NotSubscribed.encode()),
+        (
+            "Error::UnexpectedRound",
+            Error::UnexpectedRound {
+                expected: 1,
+                current: 2,
+            }
+            .encode(),
+        ),
+        ("Error::InvalidSelection", Error::InvalidSelection.encode()),
+        ("Error::TokenTimeout", Error::TokenTimeout.encode()),
+        ("Error::InvalidRaffle", Error::InvalidRaffle.encode()),
+        ("Error::SoldOut", Error::SoldOut.encode()),
+        ("Error::InvalidPricing", Error::InvalidPricing.encode()),
+        (
+            "Error::UnexpectedTokenReply",
+            Error::UnexpectedTokenReply.encode(),
+        ),
+        ("Error::EntriesPending", Error::EntriesPending.encode()),
+        (
+            "Error::InfiniteTokenReplyTimeout",
+            Error::InfiniteTokenReplyTimeout.encode(),
+        ),
+        (
+            "Error::ContractError",
+            Error::ContractError("Timeout".into()).encode(),
+        ),
+        (
+            "Notification",
+            Notification {
+                round: 1,
+                sequence: 2,
+                timestamp: 1_686_000_000_000,
+                event: Event::Cancelled { round: 1 },
+            }
+            .encode(),
+        ),
+        ("PlayerStats", stats.clone().encode()),
+        (
+            "RoundStatus::AwaitingDraw",
+            RoundStatus::AwaitingDraw.encode(),
+        ),
+        (
+            "State",
+            State {
+                admin: actor,
+                started: 1_686_000_000_000,
+                ending: 1_686_000_060_000,
+                players: vec![actor, ActorId::from(3)],
+                stakes: vec![10_000, 10_000],
+                prize_fund: 20_000,
+                participation_cost: 10_000,
+                winner: Some(actor),
+                winners: vec![actor],
+                selection: Selection::Uniform,
+                raffle: Some(Raffle {
+                    tickets: 2,
+                    unsold: UnsoldTickets::Draw,
+                }),
+                pricing: Pricing::EarlyBird {
+                    price: 5000,
+                    duration: 10_000,
+                },
+                price: 10_000,
+                fungible_token: token,
+                status: RoundStatus::Finished,
+                reserved_gas: 10_000_000_000,
+                token_reply_deposit: 5_000_000_000,
+                token_reply_timeout: Some(10),
+                existential_deposit: 500,
+                dust: 1,
+                subscribers: vec![ActorId::from(4)],
+                round: 1,
+                player_stats: vec![(actor, stats)],
+                leaderboards: vec![(token, vec![(actor, 25)])],
+            }
+            .encode(),
+        ),
+    ];
+
+    values
+        .into_iter()
+        .map(|(label, encoded)| (label.into(), format!("0x{}", hex::encode(encoded))))
+        .collect()
+}
+
+/// A snapshot file consists of sections, each one starts with a `## <name>`
+/// line.
+fn parse(snapshot: &str) -> Snapshot {
+    let mut sections = Snapshot::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+
+    for line in snapshot.lines() {
+        if let Some(name) = line.strip_prefix("## ") {
+            sections.extend(
+                current
+                    .replace((name.into(), vec![]))
+                    .map(|(name, lines)| (name, lines.join("\n"))),
+            );
+        } else if let Some((_, lines)) = &mut current {
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+    }
+
+    sections.extend(current.map(|(name, lines)| (name, lines.join("\n"))));
+
+    sections
+}
+
+fn format(snapshot: &Snapshot) -> String {
+    snapshot
+        .iter()
+        .map(|(name, section)| format!("## {name}\n{section}\n"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Describes differences between `expected` & `actual` sections, naming the
+/// affected ones.
+fn diff(expected: &Snapshot, actual: &Snapshot) -> Vec<String> {
+    let names: BTreeSet<_> = expected.keys().chain(actual.keys()).collect();
+
+    names
+        .into_iter()
+        .filter_map(|name| match (expected.get(name), actual.get(name)) {
+            (Some(expected), Some(actual)) if expected != actual => {
+                let removed = expected
+                    .lines()
+                    .filter(|line| !actual.lines().any(|other| other == *line))
+                    .map(|line| format!("- {line}"));
+                let added = actual
+                    .lines()
+                    .filter(|line| !expected.lines().any(|other| other == *line))
+                    .map(|line| format!("+ {line}"));
+
+                Some(
+                    iter::once(format!("`{name}` changed:"))
+                        .chain(removed)
+                        .chain(added)
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                )
+            }
+            (Some(_), None) => Some(format!("`{name}` is removed.")),
+            (None, Some(_)) => Some(format!("`{name}` is added.")),
+            _ => None,
+        })
+        .collect()
+}
+
+#[track_caller]
+fn assert_snapshot(path: &str, actual: Snapshot) {
+    if env::var_os("GOC_UPDATE_SNAPSHOTS").is_some() {
+        fs::write(path, format(&actual)).expect("failed to write a snapshot");
+
+        return;
+    }
+
+    let expected = parse(&fs::read_to_string(path).expect("failed to read a snapshot"));
+    let differences = diff(&expected, &actual);
+
+    assert!(
+        differences.is_empty(),
+        "the ABI differs from `{path}`. If it's deliberate, rerun with \
+        `GOC_UPDATE_SNAPSHOTS=1`.\n\n{}",
+        differences.join("\n\n")
+    );
+}
+
+#[test]
+fn metadata() {
+    assert_snapshot(METADATA, metadata_snapshot());
+}
+
+#[test]
+fn encodings() {
+    assert_snapshot(ENCODINGS, encodings_snapshot());
+}
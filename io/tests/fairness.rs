@@ -0,0 +1,257 @@
+//! Statistical tests of winner selection strategies.
+//!
+//! Seeds are consecutive block timestamps, as in the contract. Thresholds can
+//! be configured with environment variables:
+//! - `GOC_FAIRNESS_SAMPLES` — the number of samples per possible outcome
+//! (50 by default);
+//! - `GOC_FAIRNESS_Z` — the standard normal quantile of a rejection threshold
+//! (4.0 by default, i.e. a false failure probability of ~3e-5 per test);
+//! - `GOC_FAIRNESS_SEED_STEP` — the interval between seeds in milliseconds
+//! (1000 by default).
+
+use game_of_chance_io::{winner_index, Randomness, Selection, MAX_NUMBER_OF_PLAYERS};
+use std::{env, str::FromStr};
+
+const FIRST_SEED: u64 = 1_686_000_000_000;
+const NUMBERS_OF_PLAYERS: [usize; 7] = [2, 3, 7, 10, 100, 1_000, MAX_NUMBER_OF_PLAYERS];
+
+struct Config {
+    samples: usize,
+    z: f64,
+    seed_step: u64,
+}
+
+impl Config {
+    fn from_env() -> Self {
+        Self {
+            samples: var("GOC_FAIRNESS_SAMPLES", 50),
+            z: var("GOC_FAIRNESS_Z", 4.0),
+            seed_step: var("GOC_FAIRNESS_SEED_STEP", 1000),
+        }
+    }
+
+    fn seeds(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..).map(|index| FIRST_SEED + index * self.seed_step)
+    }
+}
+
+fn var<T: FromStr>(name: &str, default: T) -> T {
+    env::var(name).map_or(default, |value| {
+        value.parse().unwrap_or_else(|_| panic!("invalid `{name}`"))
+    })
+}
+
+/// The Pearson's chi-squared statistic of `observed` frequencies against the
+/// distribution proportional to `weights`.
+fn chi_squared(observed: &[u64], weights: &[f64]) -> f64 {
+    let total: u64 = observed.iter().sum();
+    let total_weight: f64 = weights.iter().sum();
+
+    observed
+        .iter()
+        .zip(weights)
+        .map(|(&observed, weight)| {
+            let expected = total as f64 * weight / total_weight;
+
+            (observed as f64 - expected).powi(2) / expected
+        })
+        .sum()
+}
+
+/// An upper critical value of the chi-squared distribution with
+/// `degrees_of_freedom` by the Wilson-Hilferty approximation.
+fn critical_value(degrees_of_freedom: usize, z: f64) -> f64 {
+    let k = degrees_of_freedom as f64;
+    let variance = 2.0 / (9.0 * k);
+
+    k * (1.0 - variance + z * variance.sqrt()).powi(3)
+}
+
+#[track_caller]
+fn assert_distribution(observed: &[u64], weights: &[f64], z: f64, description: &str) {
+    assert_scaled_distribution(observed, weights, 1.0, z, description);
+}
+
+/// Same as [`assert_distribution()`], but the statistic is multiplied by
+/// `scale`, e.g. to account for correlated `observed` frequencies.
+#[track_caller]
+fn assert_scaled_distribution(
+    observed: &[u64],
+    weights: &[f64],
+    scale: f64,
+    z: f64,
+    description: &str,
+) {
+    let statistic = chi_squared(observed, weights) * scale;
+    let critical_value = critical_value(observed.len() - 1, z);
+
+    assert!(
+        statistic <= critical_value,
+        "{description}: χ² = {statistic:.2} > {critical_value:.2}"
+    );
+}
+
+#[track_caller]
+fn assert_uniform(observed: &[u64], z: f64, description: &str) {
+    assert_distribution(observed, &vec![1.0; observed.len()], z, description);
+}
+
+#[test]
+fn single_player() {
+    let config = Config::from_env();
+
+    assert!(config
+        .seeds()
+        .take(config.samples)
+        .all(|seed| winner_index(seed, 1) == 0));
+}
+
+#[test]
+fn uniformity() {
+    let config = Config::from_env();
+
+    for number_of_players in NUMBERS_OF_PLAYERS {
+        let mut observed = vec![0; number_of_players];
+
+        for seed in config.seeds().take(config.samples * number_of_players) {
+            observed[winner_index(seed, number_of_players)] += 1;
+        }
+
+        assert_uniform(&observed, config.z, &format!("{number_of_players} players"));
+    }
+}
+
+/// Winners of consecutive game rounds must be independent, although their
+/// seeds are close.
+#[test]
+fn independence_of_consecutive_seeds() {
+    let config = Config::from_env();
+
+    for number_of_players in [2, 3, 10] {
+        let mut observed = vec![0; number_of_players * number_of_players];
+        let indexes: Vec<_> = config
+            .seeds()
+            .take(config.samples * observed.len() + 1)
+            .map(|seed| winner_index(seed, number_of_players))
+            .collect();
+
+        for pair in indexes.windows(2) {
+            observed[pair[0] * number_of_players + pair[1]] += 1;
+        }
+
+        assert_uniform(
+            &observed,
+            config.z,
+            &format!("pairs of {number_of_players} players"),
+        );
+    }
+}
+
+/// Every bit of a random number must be set in about a half of samples.
+#[test]
+fn bit_balance() {
+    let config = Config::from_env();
+    let samples = config.samples * 1000;
+    let mut observed = [[0; 2]; 16];
+
+    for seed in config.seeds().take(samples) {
+        let index = winner_index(seed, MAX_NUMBER_OF_PLAYERS);
+
+        for (bit, observed) in observed.iter_mut().enumerate() {
+            observed[(index >> bit) & 1] += 1;
+        }
+    }
+
+    for (bit, observed) in observed.iter().enumerate() {
+        assert_uniform(observed, config.z, &format!("bit {bit}"));
+    }
+}
+
+/// If a bound of random numbers isn't a power of two, some numbers would get
+/// more chances with a plain modulo reduction. The bound is chosen so that
+/// numbers below 2³⁰ would be twice as likely as others.
+#[test]
+fn no_modulo_bias() {
+    const BOUND: u128 = 3 << 30;
+
+    let config = Config::from_env();
+    let mut observed = [0; 3];
+
+    for seed in config.seeds().take(config.samples * 1000) {
+        observed[(Randomness::new(seed).below(BOUND) >> 30) as usize] += 1;
+    }
+
+    assert_uniform(&observed, config.z, "the modulo bias");
+}
+
+#[test]
+fn weighted_selection() {
+    let config = Config::from_env();
+    let stakes = [1, 2, 3, 4, 10, 0, 30];
+    let total: u128 = stakes.iter().sum();
+    let mut observed = vec![0; stakes.len()];
+
+    for seed in config.seeds().take(config.samples * total as usize) {
+        for index in Selection::Weighted.pick(seed, &stakes) {
+            observed[index] += 1;
+        }
+    }
+
+    assert_eq!(observed[5], 0, "a player without a stake won");
+
+    let (weights, observed): (Vec<_>, Vec<_>) = stakes
+        .into_iter()
+        .zip(observed)
+        .filter(|(stake, _)| *stake != 0)
+        .map(|(stake, observed)| (stake as f64, observed))
+        .unzip();
+
+    assert_distribution(&observed, &weights, config.z, "weighted selection");
+}
+
+/// Winners are drawn without replacement, so their frequencies are negatively
+/// correlated, and the plain statistic is (n - k) / (n - 1) times less than
+/// it should be. E.g., with 99 winners of 100 players, it's the statistic of
+/// the only unpicked player divided by 99, so it's rescaled.
+#[test]
+fn multiple_winners() {
+    let config = Config::from_env();
+
+    for (number_of_players, number_of_winners) in [(2, 1), (3, 2), (10, 3), (100, 99)] {
+        let stakes = vec![0; number_of_players];
+        let mut observed = vec![0; number_of_players];
+
+        for seed in config.seeds().take(config.samples * number_of_players) {
+            let winners = Selection::Multiple(number_of_winners).pick(seed, &stakes);
+            let mut unique = winners.clone();
+
+            unique.sort_unstable();
+            unique.dedup();
+
+            assert_eq!(unique.len(), winners.len(), "winners must be different");
+            assert_eq!(winners.len(), number_of_winners as usize);
+
+            for index in winners {
+                observed[index] += 1;
+            }
+        }
+
+        assert_scaled_distribution(
+            &observed,
+            &vec![1.0; number_of_players],
+            (number_of_players - 1) as f64
+                / (number_of_players - number_of_winners as usize) as f64,
+            config.z,
+            &format!("{number_of_winners} winners of {number_of_players} players"),
+        );
+    }
+}
+
+#[test]
+fn more_winners_than_players() {
+    let mut winners = Selection::Multiple(10).pick(0, &[0; 3]);
+
+    winners.sort_unstable();
+
+    assert_eq!(winners, [0, 1, 2]);
+}
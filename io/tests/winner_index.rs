@@ -0,0 +1,40 @@
+use game_of_chance_io::{winner_index, MAX_NUMBER_OF_PLAYERS};
+
+// Known seed-to-index vectors. If they change, the contract picks different
+// winners than before, and off-chain verifiers must be updated too.
+const VECTORS: [(u64, [usize; 4]); 5] = [
+    (0, [0, 0, 9, 55971]),
+    (1, [0, 2, 8, 36324]),
+    (1_000, [0, 2, 7, 9815]),
+    (1_686_000_000_000, [0, 2, 2, 52160]),
+    (u64::MAX, [0, 2, 2, 28578]),
+];
+const NUMBERS_OF_PLAYERS: [usize; 4] = [1, 3, 10, MAX_NUMBER_OF_PLAYERS];
+
+#[test]
+fn vectors() {
+    for (seed, indexes) in VECTORS {
+        for (number_of_players, index) in NUMBERS_OF_PLAYERS.into_iter().zip(indexes) {
+            assert_eq!(
+                winner_index(seed, number_of_players),
+                index,
+                "seed {seed}, {number_of_players} players"
+            );
+        }
+    }
+}
+
+#[test]
+fn index_is_in_range() {
+    for seed in 0..1000 {
+        for number_of_players in [1, 2, 3, 7, 100, MAX_NUMBER_OF_PLAYERS] {
+            assert!(winner_index(seed, number_of_players) < number_of_players);
+        }
+    }
+}
+
+#[test]
+#[should_panic]
+fn no_players() {
+    winner_index(0, 0);
+}
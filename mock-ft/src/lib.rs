@@ -0,0 +1,200 @@
+//! A test-only fungible token contract that speaks the [`FTokenAction`] &
+//! [`FTokenEvent`] protocol of the sharded FT with configurable faults.
+//!
+//! Balances aren't checked, so a transfer is processed according to the set
+//! [`Behaviour`] only. Like the sharded FT, the mock replies with the same
+//! event to a repeated transaction from the same source instead of processing
+//! it twice.
+
+#![no_std]
+
+use ft_main_io::{FTokenAction, FTokenEvent, LogicAction};
+use gstd::{exec, msg, prelude::*, ActorId, MessageId};
+
+#[cfg(feature = "binary-vendor")]
+include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
+
+/// The behaviour of the mock. It's set by the initialization payload.
+///
+/// Processed transfers are counted from 1.
+#[derive(Encode, Decode, TypeInfo, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Behaviour {
+    /// Replies with [`FTokenEvent::Ok`] to every transfer.
+    #[default]
+    AlwaysOk,
+    /// Replies with [`FTokenEvent::Err`] to the Nth transfer.
+    FailNthTransfer(u64),
+    /// Replies with [`FTokenEvent::Balance`] to the Nth transfer.
+    UnexpectedVariantOnNthTransfer(u64),
+    /// Never replies to the Nth transfer.
+    NeverReplyToNthTransfer(u64),
+    /// Processes every transaction after this number of blocks, so other
+    /// messages are executed while it's awaited.
+    DelayReplies(u32),
+}
+
+/// A transfer received by the mock.
+#[derive(Encode, Decode, TypeInfo, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transfer {
+    pub source: ActorId,
+    pub transaction_id: u64,
+    pub sender: ActorId,
+    pub recipient: ActorId,
+    pub amount: u128,
+}
+
+/// The state of the mock, read with the `state()` export.
+#[derive(Encode, Decode, TypeInfo, Debug, Default, Clone, PartialEq, Eq)]
+pub struct MockState {
+    pub behaviour: Behaviour,
+    /// All processed transfers in order. A repeated transaction is processed
+    /// again only if it wasn't replied to.
+    pub transfers: Vec<Transfer>,
+    pub balances: Vec<(ActorId, u128)>,
+}
+
+static mut MOCK: Option<Mock> = None;
+
+#[derive(Default)]
+struct Mock {
+    behaviour: Behaviour,
+    transfers: Vec<Transfer>,
+    balances: BTreeMap<ActorId, u128>,
+    replies: BTreeMap<(ActorId, u64), FTokenEvent>,
+    delayed: BTreeSet<MessageId>,
+}
+
+impl Mock {
+    fn process(&mut self, transaction_id: u64, action: LogicAction) -> FTokenEvent {
+        match action {
+            LogicAction::Mint { recipient, amount } => {
+                let balance = self.balances.entry(recipient).or_default();
+
+                *balance = balance.saturating_add(amount);
+
+                FTokenEvent::Ok
+            }
+            LogicAction::Transfer {
+                sender,
+                recipient,
+                amount,
+            } => self.transfer(transaction_id, sender, recipient, amount),
+            LogicAction::Approve { .. } => FTokenEvent::Ok,
+            _ => FTokenEvent::Err,
+        }
+    }
+
+    fn transfer(
+        &mut self,
+        transaction_id: u64,
+        sender: ActorId,
+        recipient: ActorId,
+        amount: u128,
+    ) -> FTokenEvent {
+        self.transfers.push(Transfer {
+            source: msg::source(),
+            transaction_id,
+            sender,
+            recipient,
+            amount,
+        });
+
+        let number = self.transfers.len() as u64;
+
+        match self.behaviour {
+            Behaviour::FailNthTransfer(nth) if nth == number => return FTokenEvent::Err,
+            Behaviour::UnexpectedVariantOnNthTransfer(nth) if nth == number => {
+                return FTokenEvent::Balance(amount)
+            }
+            // The message stays in the waitlist until it expires.
+            Behaviour::NeverReplyToNthTransfer(nth) if nth == number => exec::wait(),
+            _ => {}
+        }
+
+        let sender_balance = self.balances.entry(sender).or_default();
+
+        *sender_balance = sender_balance.saturating_sub(amount);
+
+        let recipient_balance = self.balances.entry(recipient).or_default();
+
+        *recipient_balance = recipient_balance.saturating_add(amount);
+
+        FTokenEvent::Ok
+    }
+}
+
+fn mock_mut() -> &'static mut Mock {
+    unsafe { MOCK.as_mut().expect("the mock isn't initialized") }
+}
+
+#[no_mangle]
+extern "C" fn init() {
+    let behaviour = msg::load().expect("failed to decode `Behaviour`");
+
+    unsafe {
+        MOCK = Some(Mock {
+            behaviour,
+            ..Default::default()
+        })
+    }
+}
+
+#[no_mangle]
+extern "C" fn handle() {
+    let mock = mock_mut();
+    let action = msg::load().expect("failed to decode `FTokenAction`");
+
+    let event = match action {
+        FTokenAction::Message {
+            transaction_id,
+            payload,
+        } => {
+            // A woken message is executed again from the start, so it's
+            // processed on the second execution.
+            if let Behaviour::DelayReplies(blocks) = mock.behaviour {
+                if mock.delayed.insert(msg::id()) {
+                    exec::wait_for(blocks);
+                }
+            }
+
+            let key = (msg::source(), transaction_id);
+
+            if let Some(event) = mock.replies.get(&key) {
+                event.clone()
+            } else {
+                let event = mock.process(transaction_id, payload);
+
+                mock.replies.insert(key, event.clone());
+
+                event
+            }
+        }
+        FTokenAction::GetBalance(actor) => {
+            FTokenEvent::Balance(mock.balances.get(&actor).copied().unwrap_or_default())
+        }
+        _ => FTokenEvent::Err,
+    };
+
+    msg::reply(event, 0).expect("failed to encode or reply from `handle()`");
+}
+
+#[no_mangle]
+extern "C" fn state() {
+    let Mock {
+        behaviour,
+        transfers,
+        balances,
+        ..
+    } = mock_mut();
+
+    let state = MockState {
+        behaviour: *behaviour,
+        transfers: transfers.clone(),
+        balances: balances
+            .iter()
+            .map(|(actor, balance)| (*actor, *balance))
+            .collect(),
+    };
+
+    msg::reply(state, 0).expect("failed to encode or reply from `state()`");
+}
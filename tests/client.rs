@@ -0,0 +1,172 @@
+use game_of_chance_client::{
+    gtest::{GtestError, GtestTransport},
+    record::{self, Message, Record, Recorder},
+    Client, ClientError, ClientResult,
+};
+use game_of_chance_io::*;
+use gstd::ActorId;
+use gtest::{Program, System, EXISTENTIAL_DEPOSIT};
+
+const ADMIN: u64 = 3;
+const PLAYERS: [u64; 2] = [4, 5];
+const PARTICIPATION_COST: u128 = 10000;
+const DURATION: u64 = 2000;
+
+fn reply<T>(result: ClientResult<T, GtestError>) -> Result<T, Error> {
+    result.map_err(|error| match error {
+        ClientError::Contract(error) => error,
+        ClientError::Transport(error) => panic!("{error}"),
+    })
+}
+
+fn initialize(system: &System) -> Program {
+    system.init_logger();
+
+    let program = Program::current(system);
+
+    assert!(!program
+        .send(
+            ADMIN,
+            Initialize {
+                admin: ADMIN.into(),
+                existential_deposit: EXISTENTIAL_DEPOSIT,
+            },
+        )
+        .main_failed());
+
+    program
+}
+
+#[tokio::test]
+async fn native_round() {
+    let system = System::new();
+    let program = initialize(&system);
+    let mut client = Client::new(GtestTransport::new(&system, program, ADMIN));
+    let ending = system.block_timestamp() + DURATION;
+
+    assert_eq!(
+        reply(
+            client
+                .start(
+                    DURATION,
+                    PARTICIPATION_COST,
+                    None,
+                    Selection::Uniform,
+                    None,
+                    Pricing::Fixed,
+                )
+                .await
+        ),
+        Ok(Event::Started {
+            round: 1,
+            ending,
+            participation_cost: PARTICIPATION_COST,
+            fungible_token: None,
+            selection: Selection::Uniform,
+            raffle: None,
+            pricing: Pricing::Fixed,
+        })
+    );
+
+    for player in PLAYERS {
+        system.mint_to(player, PARTICIPATION_COST);
+        client.transport_mut().set_sender(player);
+
+        assert_eq!(
+            reply(client.enter(Some(1), PARTICIPATION_COST).await),
+            Ok(Event::PlayerAdded {
+                round: 1,
+                player: player.into()
+            })
+        );
+    }
+
+    system.mint_to(PLAYERS[1], PARTICIPATION_COST);
+
+    assert_eq!(
+        reply(client.enter(Some(1), PARTICIPATION_COST).await),
+        Err(Error::AlreadyParticipating)
+    );
+
+    client.transport_mut().set_sender(ADMIN);
+
+    assert!(matches!(
+        reply(client.pick_winner(Some(1)).await),
+        Err(Error::EntryStageNotOver { .. })
+    ));
+
+    system.spend_blocks((DURATION / 1000) as _);
+
+    let Ok(Event::Winner { round: 1, winner, prize, dust: 0 }) = reply(client.pick_winner(None).await)
+    else {
+        panic!("failed to pick a winner");
+    };
+
+    assert!(PLAYERS.map(ActorId::from).contains(&winner));
+    assert_eq!(prize, PARTICIPATION_COST * PLAYERS.len() as u128);
+
+    let state = client.state().await.expect("failed to read the state");
+
+    assert_eq!(state.round, 1);
+    assert_eq!(state.status, RoundStatus::Finished);
+    assert_eq!(state.winner, Some(winner));
+    assert_eq!(state.players, PLAYERS.map(ActorId::from));
+}
+
+#[tokio::test]
+async fn recording() {
+    let system = System::new();
+    let program = initialize(&system);
+    let mut client = Client::new(Recorder::new(
+        GtestTransport::new(&system, program, ADMIN),
+        vec![],
+    ));
+    let now = system.block_timestamp();
+    let ending = now + DURATION;
+
+    let started = client
+        .start(
+            DURATION,
+            PARTICIPATION_COST,
+            None,
+            Selection::Uniform,
+            None,
+            Pricing::Fixed,
+        )
+        .await
+        .unwrap();
+
+    assert!(client.pick_winner(None).await.is_err());
+
+    let log = String::from_utf8(client.transport().writer().clone()).unwrap();
+    let records: Vec<_> = record::parse(&log)
+        .map(|(_, record)| record.unwrap())
+        .collect();
+    let record = |message| Record {
+        timestamp: now,
+        source: ADMIN.into(),
+        value: 0,
+        message,
+    };
+
+    assert_eq!(
+        records,
+        [
+            record(Message::Handle(
+                Action::Start {
+                    duration: DURATION,
+                    participation_cost: PARTICIPATION_COST,
+                    fungible_token: None,
+                    selection: Selection::Uniform,
+                    raffle: None,
+                    pricing: Pricing::Fixed,
+                },
+                Ok(started)
+            )),
+            record(Message::Handle(
+                Action::PickWinner(None),
+                Err(Error::EntryStageNotOver { ending, now })
+            ))
+        ]
+    );
+}
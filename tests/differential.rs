@@ -0,0 +1,454 @@
+//! Differential testing of the contract against a reference [`model`].
+//!
+//! The same random streams of actions are sent to the `gtest`-hosted contract
+//! & the model. Every reply and the state after every step must match, and
+//! the first divergence is reported after shrinking the stream.
+
+use futures::executor;
+use game_of_chance_client::{gtest::GtestTransport, Transport};
+use game_of_chance_io::*;
+use gstd::ActorId;
+use gtest::{Program, System, EXISTENTIAL_DEPOSIT};
+use model::{Context, Model};
+use proptest::{collection, prelude::*};
+use std::fmt::Debug;
+use utils::{common::Program as _, FungibleToken, FOREIGN_USER};
+
+mod utils;
+
+#[path = "differential/model.rs"]
+mod model;
+
+const ADMIN: u64 = 3;
+const PLAYERS: [u64; 4] = [4, 5, 6, 7];
+const NATIVE_BALANCE: u128 = EXISTENTIAL_DEPOSIT * 1000;
+// Small balances make some transfers fail.
+const FT_BALANCES: [u128; PLAYERS.len()] = [
+    0,
+    EXISTENTIAL_DEPOSIT,
+    EXISTENTIAL_DEPOSIT * 3,
+    EXISTENTIAL_DEPOSIT * 100,
+];
+const MAX_NUMBER_OF_STEPS: usize = 32;
+
+#[derive(Debug, Clone, Copy)]
+enum Value {
+    Exact,
+    Zero,
+    Less,
+    More,
+    /// More by less than the existential deposit, so the excess is kept as
+    /// dust.
+    MoreByDust,
+    /// Less than the existential deposit, so it's kept as dust.
+    Dust,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Round {
+    Any,
+    Current,
+    Next,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Start {
+        from_admin: bool,
+        blocks: u64,
+        cost_multiplier: u128,
+        in_tokens: bool,
+        selection: Selection,
+        raffle: Option<Raffle>,
+        pricing: Pricing,
+    },
+    Enter {
+        player: usize,
+        value: Value,
+        round: Round,
+    },
+    PickWinner {
+        from_admin: bool,
+        round: Round,
+    },
+    RecoverValue {
+        from_admin: bool,
+        amount_multiplier: u128,
+    },
+    /// Reserves [`SETTLEMENT_GAS`], so the reservation can be reused on
+    /// [`Action::Start`] if it outlives the players entry stage.
+    ReserveGas {
+        from_admin: bool,
+        duration: u32,
+    },
+    GasReservation {
+        from_admin: bool,
+    },
+    ConfigureTokenCalls {
+        from_admin: bool,
+        reply_timeout: Option<u32>,
+    },
+    /// Subscribes a player or [`ActorId::zero()`].
+    Subscribe(Option<usize>),
+    Unsubscribe(usize),
+    SpendBlocks(u32),
+}
+
+fn step() -> impl Strategy<Value = Step> {
+    let value = prop_oneof![
+        Just(Value::Exact),
+        Just(Value::Zero),
+        Just(Value::Less),
+        Just(Value::More),
+        Just(Value::MoreByDust),
+        Just(Value::Dust),
+    ];
+    let round = || prop_oneof![Just(Round::Any), Just(Round::Current), Just(Round::Next)];
+    let selection = prop_oneof![
+        Just(Selection::Uniform),
+        Just(Selection::Weighted),
+        (0..4u32).prop_map(Selection::Multiple),
+    ];
+    let raffle =
+        proptest::option::of(
+            (0..4u32, any::<bool>()).prop_map(|(tickets, refund)| Raffle {
+                tickets,
+                unsold: if refund {
+                    UnsoldTickets::Refund
+                } else {
+                    UnsoldTickets::Draw
+                },
+            }),
+        );
+
+    // Some early-bird prices & floors exceed the participation cost, so such
+    // pricings are invalid.
+    let pricing = prop_oneof![
+        Just(Pricing::Fixed),
+        (0..3u128).prop_map(|multiplier| Pricing::Linear {
+            step: EXISTENTIAL_DEPOSIT * multiplier,
+        }),
+        (0..5000u32).prop_map(|rate| Pricing::Exponential { rate }),
+        (0..4u128, 0..4u64).prop_map(|(multiplier, blocks)| Pricing::EarlyBird {
+            price: EXISTENTIAL_DEPOSIT * multiplier,
+            duration: blocks * 1000,
+        }),
+        (0..4u128).prop_map(|multiplier| Pricing::DutchAuction {
+            floor: EXISTENTIAL_DEPOSIT * multiplier,
+        }),
+    ];
+
+    prop_oneof![
+        2 => (
+            any::<bool>(),
+            1..4u64,
+            1..4u128,
+            any::<bool>(),
+            selection,
+            raffle,
+            pricing,
+        )
+            .prop_map(
+                |(from_admin, blocks, cost_multiplier, in_tokens, selection, raffle, pricing)| {
+                    Step::Start {
+                        from_admin,
+                        blocks,
+                        cost_multiplier,
+                        in_tokens,
+                        selection,
+                        raffle,
+                        pricing,
+                    }
+                }
+            ),
+        6 => (0..PLAYERS.len(), value, round()).prop_map(|(player, value, round)| Step::Enter {
+            player,
+            value,
+            round,
+        }),
+        2 => (any::<bool>(), round())
+            .prop_map(|(from_admin, round)| Step::PickWinner { from_admin, round }),
+        1 => (any::<bool>(), 0..4u128).prop_map(|(from_admin, amount_multiplier)| {
+            Step::RecoverValue {
+                from_admin,
+                amount_multiplier,
+            }
+        }),
+        1 => (any::<bool>(), prop_oneof![1..4u32, Just(20_000)])
+            .prop_map(|(from_admin, duration)| Step::ReserveGas { from_admin, duration }),
+        1 => any::<bool>().prop_map(|from_admin| Step::GasReservation { from_admin }),
+        1 => (any::<bool>(), proptest::option::of(0..4u32)).prop_map(
+            |(from_admin, reply_timeout)| Step::ConfigureTokenCalls {
+                from_admin,
+                reply_timeout,
+            }
+        ),
+        1 => proptest::option::of(0..PLAYERS.len()).prop_map(Step::Subscribe),
+        1 => (0..PLAYERS.len()).prop_map(Step::Unsubscribe),
+        2 => (1..4u32).prop_map(Step::SpendBlocks),
+    ]
+}
+
+/// Names fields of two [`State`]s that differ.
+macro_rules! state_diff {
+    ($model:expr, $contract:expr, $($field:ident),+ $(,)?) => {{
+        // Destructuring makes sure a new field isn't missed.
+        let State { $($field),+ } = $contract;
+        let mut diff = vec![];
+
+        $(
+            if $model.$field != *$field {
+                diff.push(format!(
+                    "{}: the model has {:?}, the contract has {:?}",
+                    stringify!($field),
+                    $model.$field,
+                    $field,
+                ));
+            }
+        )+
+
+        diff
+    }};
+}
+
+struct Harness<'a> {
+    system: &'a System,
+    transport: GtestTransport<'a>,
+    fungible_token: ActorId,
+    model: Model,
+}
+
+impl<'a> Harness<'a> {
+    fn new(system: &'a System) -> Self {
+        let mut fungible_token = FungibleToken::initialize(system);
+        let program = Program::current(system);
+        let program_id: [u8; 32] = program.id().into();
+        let initialize = Initialize {
+            admin: ADMIN.into(),
+            existential_deposit: EXISTENTIAL_DEPOSIT,
+        };
+
+        system.mint_to(program.id(), EXISTENTIAL_DEPOSIT);
+        assert!(!program.send(FOREIGN_USER, initialize).main_failed());
+
+        let mut model = Model::new(program_id.into(), initialize, EXISTENTIAL_DEPOSIT);
+
+        for (player, balance) in PLAYERS.into_iter().zip(FT_BALANCES) {
+            system.mint_to(player, NATIVE_BALANCE);
+
+            if balance != 0 {
+                fungible_token.mint(player, balance);
+                model.mint_tokens(player.into(), balance);
+            }
+
+            fungible_token.approve(player, program_id, u128::MAX);
+        }
+
+        Self {
+            system,
+            transport: GtestTransport::new(system, program, FOREIGN_USER),
+            fungible_token: fungible_token.actor_id(),
+            model,
+        }
+    }
+
+    /// Executes `step` on both sides & describes the divergence if there's
+    /// one.
+    fn execute(&mut self, step: Step) -> Result<(), String> {
+        let state = self.model_state();
+        let round = |round| match round {
+            Round::Any => None,
+            Round::Current => Some(state.round),
+            Round::Next => Some(state.round + 1),
+        };
+        let admin_or_player = |from_admin| if from_admin { ADMIN } else { PLAYERS[0] };
+        let (from, action, value) = match step {
+            Step::Start {
+                from_admin,
+                blocks,
+                cost_multiplier,
+                in_tokens,
+                selection,
+                raffle,
+                pricing,
+            } => (
+                admin_or_player(from_admin),
+                Action::Start {
+                    duration: blocks * 1000,
+                    participation_cost: EXISTENTIAL_DEPOSIT * cost_multiplier,
+                    fungible_token: in_tokens.then_some(self.fungible_token),
+                    selection,
+                    raffle,
+                    pricing,
+                },
+                0,
+            ),
+            Step::Enter {
+                player,
+                value,
+                round: expected,
+            } => {
+                let cost = state.price;
+                let value = match value {
+                    Value::Exact if state.fungible_token.is_some() => 0,
+                    Value::Exact => cost,
+                    Value::Zero => 0,
+                    Value::Less => cost.saturating_sub(EXISTENTIAL_DEPOSIT),
+                    Value::More => cost + EXISTENTIAL_DEPOSIT,
+                    Value::MoreByDust => cost + EXISTENTIAL_DEPOSIT / 2,
+                    Value::Dust => EXISTENTIAL_DEPOSIT / 2,
+                };
+
+                (PLAYERS[player], Action::Enter(round(expected)), value)
+            }
+            Step::PickWinner {
+                from_admin,
+                round: expected,
+            } => (
+                admin_or_player(from_admin),
+                Action::PickWinner(round(expected)),
+                0,
+            ),
+            Step::RecoverValue {
+                from_admin,
+                amount_multiplier,
+            } => (
+                admin_or_player(from_admin),
+                Action::RecoverValue {
+                    to: ADMIN.into(),
+                    amount: EXISTENTIAL_DEPOSIT * amount_multiplier,
+                },
+                0,
+            ),
+            Step::ReserveGas {
+                from_admin,
+                duration,
+            } => (
+                admin_or_player(from_admin),
+                Action::ReserveGas {
+                    amount: SETTLEMENT_GAS,
+                    duration,
+                },
+                0,
+            ),
+            Step::GasReservation { from_admin } => {
+                (admin_or_player(from_admin), Action::GasReservation, 0)
+            }
+            Step::ConfigureTokenCalls {
+                from_admin,
+                reply_timeout,
+            } => (
+                admin_or_player(from_admin),
+                Action::ConfigureTokenCalls {
+                    reply_deposit: 0,
+                    reply_timeout,
+                },
+                0,
+            ),
+            Step::Subscribe(player) => (
+                ADMIN,
+                Action::Subscribe(player.map_or(ActorId::zero(), |index| PLAYERS[index].into())),
+                0,
+            ),
+            Step::Unsubscribe(player) => (ADMIN, Action::Unsubscribe(PLAYERS[player].into()), 0),
+            Step::SpendBlocks(blocks) => {
+                self.system.spend_blocks(blocks);
+
+                return self.compare_states();
+            }
+        };
+
+        // `gtest` processes a message in the current block.
+        let context = Context {
+            source: from.into(),
+            value,
+            now: self.system.block_timestamp(),
+            height: self.system.block_height(),
+        };
+        let expected = self.model.handle(context, action);
+
+        self.transport.set_sender(from);
+
+        let reply = executor::block_on(self.transport.send(action, value))
+            .map_err(|error| format!("failed to send {action:?}: {error}"))?;
+
+        compare("reply", &expected, &reply)?;
+
+        self.compare_states()
+    }
+
+    fn model_state(&self) -> State {
+        self.model
+            .state(self.system.block_timestamp(), self.system.block_height())
+    }
+
+    fn compare_states(&self) -> Result<(), String> {
+        let model = self.model_state();
+        let contract = executor::block_on(self.transport.state())
+            .map_err(|error| format!("failed to read the state: {error}"))?;
+        let diff = state_diff!(
+            model,
+            &contract,
+            admin,
+            started,
+            ending,
+            players,
+            stakes,
+            prize_fund,
+            participation_cost,
+            winner,
+            winners,
+            selection,
+            raffle,
+            pricing,
+            price,
+            fungible_token,
+            status,
+            reserved_gas,
+            token_reply_deposit,
+            token_reply_timeout,
+            existential_deposit,
+            dust,
+            subscribers,
+            round,
+            player_stats,
+            leaderboards,
+        );
+
+        if diff.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("the state diverged:\n{}", diff.join("\n")))
+        }
+    }
+}
+
+fn compare<T: Debug + PartialEq>(what: &str, model: &T, contract: &T) -> Result<(), String> {
+    if model == contract {
+        Ok(())
+    } else {
+        Err(format!(
+            "the {what} diverged: the model has {model:?}, the contract has {contract:?}"
+        ))
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        // Every case deploys the whole sharded FT stack.
+        cases: 32,
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn differential(steps in collection::vec(step(), 1..MAX_NUMBER_OF_STEPS)) {
+        let system = utils::initialize_system();
+        let mut harness = Harness::new(&system);
+
+        for (index, step) in steps.iter().enumerate() {
+            let result = harness.execute(*step);
+
+            prop_assert!(result.is_ok(), "step {} ({step:?}): {}", index + 1, result.unwrap_err());
+        }
+    }
+}
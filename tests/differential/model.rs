@@ -0,0 +1,755 @@
+//! A reference model of the contract.
+//!
+//! The model is written from the documentation of [`Action`]s rather than from
+//! the contract code, and keeps its state in the public [`State`], so it only
+//! tracks what users can observe. It assumes that an FT contract rejects
+//! transfers only because of an insufficient balance, and that enough gas is
+//! attached to every [`Action::ReserveGas`].
+
+use core::cmp::Reverse;
+use game_of_chance_io::*;
+use gstd::{prelude::*, ActorId};
+
+/// The number of blocks a settlement reservation outlives the players entry
+/// stage.
+const SETTLEMENT_RESERVATION_MARGIN: u32 = 14_400;
+const MAX_SETTLEMENT_RESERVATION_DURATION: u32 = 2_592_000;
+
+/// The context in which an action is processed.
+#[derive(Debug, Clone, Copy)]
+pub struct Context {
+    pub source: ActorId,
+    pub value: u128,
+    pub now: u64,
+    pub height: u32,
+}
+
+#[derive(Debug)]
+pub struct Model {
+    program: ActorId,
+    /// The state with [`RoundStatus::AwaitingDraw`] never stored.
+    state: State,
+    /// Amounts & expiration heights of gas reservations.
+    reservations: Vec<(u64, u32)>,
+    /// The native value the contract owns.
+    balance: u128,
+    ft_balances: BTreeMap<ActorId, u128>,
+    /// Never evicted, since the generated players are far fewer than
+    /// [`MAX_NUMBER_OF_TRACKED_PLAYERS`].
+    player_stats: BTreeMap<ActorId, PlayerStats>,
+}
+
+impl Model {
+    pub fn new(program: ActorId, initialize: Initialize, balance: u128) -> Self {
+        Self {
+            program,
+            state: State {
+                admin: initialize.admin,
+                existential_deposit: initialize.existential_deposit,
+                token_reply_timeout: Some(DEFAULT_TOKEN_REPLY_TIMEOUT),
+                ..Default::default()
+            },
+            reservations: vec![],
+            balance,
+            ft_balances: BTreeMap::new(),
+            player_stats: BTreeMap::new(),
+        }
+    }
+
+    pub fn mint_tokens(&mut self, actor: ActorId, amount: u128) {
+        *self.ft_balances.entry(actor).or_default() += amount;
+    }
+
+    pub fn state(&self, now: u64, height: u32) -> State {
+        let mut state = self.state.clone();
+
+        if state.status == RoundStatus::Entry && (state.ending <= now || self.is_sold_out()) {
+            state.status = RoundStatus::AwaitingDraw;
+        }
+
+        state.price = self.price(now);
+        state.reserved_gas = self
+            .reservations
+            .iter()
+            .filter(|(_, expiration)| *expiration > height)
+            .map(|(amount, _)| amount)
+            .sum();
+        state.player_stats = self
+            .player_stats
+            .iter()
+            .map(|(player, stats)| (*player, stats.clone()))
+            .collect();
+
+        // Leaderboards are ordered by currencies, then by descending winnings &
+        // ascending player IDs.
+        let mut leaderboards = BTreeMap::<_, Leaderboard>::new();
+
+        for (player, stats) in &self.player_stats {
+            for (currency, currency_stats) in &stats.currencies {
+                if currency_stats.won != 0 {
+                    leaderboards
+                        .entry(*currency)
+                        .or_default()
+                        .push((*player, currency_stats.won));
+                }
+            }
+        }
+
+        state.leaderboards = leaderboards
+            .into_iter()
+            .map(|(currency, mut leaderboard)| {
+                leaderboard.sort_by_key(|(player, won)| (Reverse(*won), *player));
+
+                (currency, leaderboard)
+            })
+            .collect();
+
+        state
+    }
+
+    pub fn handle(&mut self, context: Context, action: Action) -> Result<Event, Error> {
+        self.balance += context.value;
+        self.reservations
+            .retain(|(_, expiration)| *expiration > context.height);
+
+        match action {
+            Action::Start {
+                duration,
+                participation_cost,
+                fungible_token,
+                selection,
+                raffle,
+                pricing,
+            } => self.start(
+                context,
+                duration,
+                participation_cost,
+                fungible_token,
+                selection,
+                raffle,
+                pricing,
+            ),
+            Action::Enter(round) => self.enter(context, round),
+            Action::PickWinner(round) => self.pick_winner(context, round),
+            Action::RecoverValue { to, amount } => self.recover_value(context, to, amount),
+            Action::ReserveGas { amount, duration } => self.reserve_gas(context, amount, duration),
+            Action::GasReservation => self.gas_reservation(context),
+            Action::ConfigureTokenCalls {
+                reply_deposit,
+                reply_timeout,
+            } => self.configure_token_calls(context, reply_deposit, reply_timeout),
+            Action::Subscribe(subscriber) => self.subscribe(context, subscriber),
+            Action::Unsubscribe(subscriber) => self.unsubscribe(context, subscriber),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn start(
+        &mut self,
+        context: Context,
+        duration: u64,
+        participation_cost: u128,
+        fungible_token: Option<ActorId>,
+        selection: Selection,
+        raffle: Option<Raffle>,
+        pricing: Pricing,
+    ) -> Result<Event, Error> {
+        self.check_admin(context)?;
+
+        let status = self.state(context.now, context.height).status;
+
+        if !matches!(
+            status,
+            RoundStatus::NotStarted | RoundStatus::Finished | RoundStatus::Cancelled
+        ) {
+            return Err(Error::RoundIsActive {
+                status,
+                ending: self.state.ending,
+            });
+        }
+
+        if fungible_token == Some(ActorId::zero()) {
+            return Err(Error::ZeroActorId);
+        }
+
+        if fungible_token.is_some() && !is_finite(self.state.token_reply_timeout) {
+            return Err(Error::InfiniteTokenReplyTimeout);
+        }
+
+        if selection == Selection::Multiple(0) {
+            return Err(Error::InvalidSelection);
+        }
+
+        let tickets = raffle.map(|raffle| raffle.tickets as usize);
+
+        if matches!(tickets, Some(tickets) if tickets == 0 || tickets > MAX_NUMBER_OF_PLAYERS) {
+            return Err(Error::InvalidRaffle);
+        }
+
+        let limit = match pricing {
+            Pricing::EarlyBird { price, .. } => price,
+            Pricing::DutchAuction { floor } => floor,
+            _ => 0,
+        };
+
+        if limit > participation_cost {
+            return Err(Error::InvalidPricing);
+        }
+
+        // A reservation lives for the duration in seconds plus a margin.
+        let reservation_duration = (duration / 1000 + SETTLEMENT_RESERVATION_MARGIN as u64)
+            .min(MAX_SETTLEMENT_RESERVATION_DURATION as u64)
+            as u32;
+
+        let expiration = context.height + reservation_duration;
+
+        // An unused reservation is reused if it's big enough & outlives the
+        // players entry stage by at least a half of the margin.
+        if !self.reservations.iter().any(|&(amount, reserved_until)| {
+            amount >= SETTLEMENT_GAS
+                && reserved_until >= expiration - SETTLEMENT_RESERVATION_MARGIN / 2
+        }) {
+            self.reservations.push((SETTLEMENT_GAS, expiration));
+        }
+
+        let state = &mut self.state;
+
+        state.round += 1;
+        state.started = context.now;
+        state.ending = context.now + duration;
+        state.players.clear();
+        state.stakes.clear();
+        state.prize_fund = 0;
+        state.participation_cost = participation_cost;
+        state.fungible_token = fungible_token;
+        state.selection = selection;
+        state.raffle = raffle;
+        state.pricing = pricing;
+        state.winner = None;
+        state.winners.clear();
+        state.status = RoundStatus::Entry;
+
+        Ok(Event::Started {
+            round: state.round,
+            ending: state.ending,
+            participation_cost,
+            fungible_token,
+            selection,
+            raffle,
+            pricing,
+        })
+    }
+
+    fn enter(&mut self, context: Context, round: Option<u64>) -> Result<Event, Error> {
+        self.check_round(round)?;
+
+        let state = &self.state;
+
+        if state.round == 0 {
+            return Err(Error::NoRoundStarted);
+        }
+
+        if self.is_sold_out() {
+            return Err(Error::SoldOut);
+        }
+
+        if state.ending <= context.now {
+            return Err(Error::EntryStageOver {
+                ending: state.ending,
+                now: context.now,
+            });
+        }
+
+        if state.players.contains(&context.source) {
+            return Err(Error::AlreadyParticipating);
+        }
+
+        let cost = self.price(context.now);
+
+        if state.fungible_token.is_some() {
+            let program = self.program;
+
+            self.transfer_tokens(context.source, program, cost)?;
+        } else if context.value < cost {
+            self.refund_value(context.value);
+
+            return Err(Error::InvalidParticipationCost {
+                expected: cost,
+                sent: context.value,
+            });
+        } else {
+            self.refund_value(context.value - cost);
+        }
+
+        let (round, currency) = (self.state.round, self.state.fungible_token);
+        let stats = self.player_stats.entry(context.source).or_default();
+
+        stats.rounds_entered += 1;
+        stats.last_round = round;
+        stats.currencies.entry(currency).or_default().spent += cost;
+
+        let state = &mut self.state;
+
+        state.players.push(context.source);
+        state.stakes.push(cost);
+        state.prize_fund += cost;
+
+        Ok(Event::PlayerAdded {
+            round: state.round,
+            player: context.source,
+        })
+    }
+
+    fn refund_value(&mut self, value: u128) {
+        // Refunds less than the existential deposit are kept as dust.
+        if value < self.state.existential_deposit {
+            self.state.dust += value;
+        } else {
+            self.balance -= value;
+        }
+    }
+
+    fn pick_winner(&mut self, context: Context, round: Option<u64>) -> Result<Event, Error> {
+        self.check_round(round)?;
+
+        match self.state.status {
+            RoundStatus::NotStarted => return Err(Error::NoRoundStarted),
+            RoundStatus::Finished => {
+                return Err(Error::WinnerAlreadyPicked {
+                    winner: self.state.winners[0],
+                })
+            }
+            RoundStatus::Cancelled => return Err(Error::RoundIsCancelled),
+            _ => {}
+        }
+
+        if context.source == self.state.admin {
+            if self.state.ending > context.now && !self.is_sold_out() {
+                return Err(Error::EntryStageNotOver {
+                    ending: self.state.ending,
+                    now: context.now,
+                });
+            }
+        } else if context.source != self.program {
+            return Err(Error::AccessRestricted);
+        }
+
+        let round = self.state.round;
+
+        if self.state.players.is_empty() {
+            self.state.status = RoundStatus::Cancelled;
+
+            return Ok(Event::Cancelled { round });
+        }
+
+        if matches!(self.state.raffle, Some(raffle) if raffle.unsold == UnsoldTickets::Refund)
+            && !self.is_sold_out()
+        {
+            return self.refund(round);
+        }
+
+        // Winners are picked with the documented algorithm seeded with the
+        // block timestamp.
+        let winners: Vec<_> = self
+            .state
+            .selection
+            .pick(context.now, &self.state.stakes)
+            .into_iter()
+            .map(|index| self.state.players[index])
+            .collect();
+        let mut dust = 0;
+        let prizes = if self.state.fungible_token.is_some() {
+            let prizes = split(self.state.prize_fund, winners.len());
+
+            for (&winner, &prize) in winners.iter().zip(&prizes) {
+                self.transfer_tokens(self.program, winner, prize)?;
+            }
+
+            self.take_reservation();
+
+            prizes
+        } else {
+            let payout = self.state.prize_fund + self.state.dust;
+            // Prizes less than the existential deposit are kept as dust.
+            let prizes: Vec<_> = split(payout, winners.len())
+                .into_iter()
+                .map(|prize| {
+                    if prize < self.state.existential_deposit {
+                        dust += prize;
+
+                        0
+                    } else {
+                        prize
+                    }
+                })
+                .collect();
+
+            if dust != payout {
+                self.take_reservation();
+            }
+
+            self.balance -= payout - dust;
+            self.state.dust = dust;
+
+            prizes
+        };
+
+        for (&winner, &prize) in winners.iter().zip(&prizes) {
+            let stats = self.player_stats.entry(winner).or_default();
+
+            stats.wins += 1;
+            stats.last_round = round;
+            stats
+                .currencies
+                .entry(self.state.fungible_token)
+                .or_default()
+                .won += prize;
+        }
+
+        self.state.winner = Some(winners[0]);
+        self.state.winners = winners.clone();
+        self.state.status = RoundStatus::Finished;
+
+        Ok(if let Selection::Multiple(_) = self.state.selection {
+            Event::Winners {
+                round,
+                winners: winners.into_iter().zip(prizes).collect(),
+                dust,
+            }
+        } else {
+            Event::Winner {
+                round,
+                winner: winners[0],
+                prize: prizes[0],
+                dust,
+            }
+        })
+    }
+
+    /// Sends participation costs back to players of a raffle with unsold
+    /// tickets.
+    fn refund(&mut self, round: u64) -> Result<Event, Error> {
+        let (mut amount, mut dust) = (0, 0);
+
+        for (player, stake) in self
+            .state
+            .players
+            .clone()
+            .into_iter()
+            .zip(self.state.stakes.clone())
+        {
+            if self.state.fungible_token.is_some() {
+                self.transfer_tokens(self.program, player, stake)?;
+                amount += stake;
+            } else if stake < self.state.existential_deposit {
+                dust += stake;
+            } else {
+                self.balance -= stake;
+                amount += stake;
+            }
+
+            // Refunded participation costs aren't counted as spent.
+            self.player_stats
+                .entry(player)
+                .or_default()
+                .currencies
+                .entry(self.state.fungible_token)
+                .or_default()
+                .spent -= stake;
+        }
+
+        self.state.dust += dust;
+        self.state.status = RoundStatus::Cancelled;
+        self.take_reservation();
+
+        Ok(Event::Refunded {
+            round,
+            amount,
+            dust,
+        })
+    }
+
+    fn recover_value(
+        &mut self,
+        context: Context,
+        to: ActorId,
+        amount: u128,
+    ) -> Result<Event, Error> {
+        self.check_admin(context)?;
+
+        if to.is_zero() {
+            return Err(Error::ZeroActorId);
+        }
+
+        // The prize fund of an unsettled native round & the dust are owed to
+        // players.
+        let state = &self.state;
+        let is_unsettled = matches!(state.status, RoundStatus::Entry | RoundStatus::Settling);
+        let owed = if is_unsettled && state.fungible_token.is_none() {
+            state.prize_fund + state.dust
+        } else {
+            state.dust
+        };
+        let surplus = self.balance.saturating_sub(owed);
+
+        if amount > surplus {
+            return Err(Error::InsufficientSurplus { surplus });
+        }
+
+        if amount < state.existential_deposit {
+            return Err(Error::BelowExistentialDeposit {
+                existential_deposit: state.existential_deposit,
+            });
+        }
+
+        self.balance -= amount;
+
+        Ok(Event::ValueRecovered { to, amount })
+    }
+
+    /// Assumes that enough gas is attached to reserve `amount`.
+    fn reserve_gas(
+        &mut self,
+        context: Context,
+        amount: u64,
+        duration: u32,
+    ) -> Result<Event, Error> {
+        self.check_admin(context)?;
+
+        if self.reservations.len() == MAX_NUMBER_OF_RESERVATIONS {
+            return Err(Error::MemoryLimitExceeded {
+                limit: Limit::Reservations,
+                maximum: MAX_NUMBER_OF_RESERVATIONS as u32,
+            });
+        }
+
+        let expiration = context.height.saturating_add(duration);
+
+        self.reservations.push((amount, expiration));
+
+        Ok(Event::GasReserved { amount, expiration })
+    }
+
+    fn gas_reservation(&self, context: Context) -> Result<Event, Error> {
+        self.check_admin(context)?;
+
+        Ok(Event::GasReservation {
+            reserved_gas: self.state(context.now, context.height).reserved_gas,
+            expiration: self
+                .reservations
+                .iter()
+                .map(|(_, expiration)| *expiration)
+                .min(),
+        })
+    }
+
+    fn configure_token_calls(
+        &mut self,
+        context: Context,
+        reply_deposit: u64,
+        reply_timeout: Option<u32>,
+    ) -> Result<Event, Error> {
+        self.check_admin(context)?;
+
+        let status = self.state(context.now, context.height).status;
+        let is_over = matches!(
+            status,
+            RoundStatus::NotStarted | RoundStatus::Finished | RoundStatus::Cancelled
+        );
+
+        if self.state.fungible_token.is_some() && !is_over && !is_finite(reply_timeout) {
+            return Err(Error::InfiniteTokenReplyTimeout);
+        }
+
+        self.state.token_reply_deposit = reply_deposit;
+        self.state.token_reply_timeout = reply_timeout;
+
+        Ok(Event::TokenCallsConfigured {
+            reply_deposit,
+            reply_timeout,
+        })
+    }
+
+    fn subscribe(&mut self, context: Context, subscriber: ActorId) -> Result<Event, Error> {
+        self.check_admin(context)?;
+
+        let subscribers = &mut self.state.subscribers;
+
+        if subscriber.is_zero() {
+            return Err(Error::ZeroActorId);
+        }
+
+        if subscribers.contains(&subscriber) {
+            return Err(Error::AlreadySubscribed);
+        }
+
+        if subscribers.len() == MAX_NUMBER_OF_SUBSCRIBERS {
+            return Err(Error::MemoryLimitExceeded {
+                limit: Limit::Subscribers,
+                maximum: MAX_NUMBER_OF_SUBSCRIBERS as u32,
+            });
+        }
+
+        subscribers.push(subscriber);
+
+        Ok(Event::Subscribed(subscriber))
+    }
+
+    fn unsubscribe(&mut self, context: Context, subscriber: ActorId) -> Result<Event, Error> {
+        self.check_admin(context)?;
+
+        let subscribers = &mut self.state.subscribers;
+        let len = subscribers.len();
+
+        subscribers.retain(|actor| *actor != subscriber);
+
+        if subscribers.len() == len {
+            return Err(Error::NotSubscribed);
+        }
+
+        Ok(Event::Unsubscribed(subscriber))
+    }
+
+    /// The price of the next entry at `now` as documented for [`Pricing`],
+    /// saturating at [`u128::MAX`].
+    fn price(&self, now: u64) -> u128 {
+        let state = &self.state;
+        let cost = state.participation_cost;
+        let entries = state.players.len() as u128;
+        let elapsed = now.saturating_sub(state.started);
+
+        match state.pricing {
+            Pricing::Fixed => cost,
+            Pricing::Linear { step } => cost.saturating_add(step.saturating_mul(entries)),
+            Pricing::Exponential { rate } => {
+                // Numbers are `(significand, exponent)` pairs with 19
+                // significant digits, and extra digits are dropped after every
+                // product.
+                let mul = |(a, a_exponent): (u128, i32), (b, b_exponent): (u128, i32)| {
+                    let (mut significand, mut exponent) =
+                        (a * b, a_exponent.saturating_add(b_exponent));
+
+                    while significand >= 10u128.pow(19) {
+                        significand /= 10;
+                        exponent = exponent.saturating_add(1);
+                    }
+
+                    (significand, exponent)
+                };
+                let (mut base, mut factor, mut power) =
+                    ((10_000 + rate as u128, -4), (1, 0), entries);
+
+                while power > 0 {
+                    if power % 2 == 1 {
+                        factor = mul(factor, base);
+                    }
+
+                    base = mul(base, base);
+                    power /= 2;
+                }
+
+                let (significand, exponent) = factor;
+
+                if cost == 0 {
+                    0
+                } else if exponent >= 0 {
+                    10u128
+                        .checked_pow(exponent as u32)
+                        .and_then(|power| cost.checked_mul(significand)?.checked_mul(power))
+                        .unwrap_or(u128::MAX)
+                } else {
+                    // The factor isn't less than 1, so the divisor doesn't
+                    // exceed 10^18, and `cost * significand / divisor` is
+                    // split to not overflow.
+                    let divisor = 10u128.pow(exponent.unsigned_abs());
+
+                    (cost / divisor)
+                        .saturating_mul(significand)
+                        .saturating_add(cost % divisor * significand / divisor)
+                }
+            }
+            Pricing::EarlyBird { price, duration } if elapsed < duration => price,
+            Pricing::EarlyBird { .. } => cost,
+            Pricing::DutchAuction { floor } => {
+                let duration = state.ending - state.started;
+
+                if elapsed >= duration {
+                    floor
+                } else {
+                    // The decay is rounded down, so the price is rounded up.
+                    cost - (cost - floor) * elapsed as u128 / duration as u128
+                }
+            }
+        }
+    }
+
+    fn is_sold_out(&self) -> bool {
+        matches!(self.state.raffle, Some(raffle) if self.state.players.len() >= raffle.tickets as usize)
+    }
+
+    fn check_admin(&self, context: Context) -> Result<(), Error> {
+        if context.source == self.state.admin {
+            Ok(())
+        } else {
+            Err(Error::AccessRestricted)
+        }
+    }
+
+    fn check_round(&self, round: Option<u64>) -> Result<(), Error> {
+        match round {
+            Some(expected) if expected != self.state.round => Err(Error::UnexpectedRound {
+                expected,
+                current: self.state.round,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn transfer_tokens(
+        &mut self,
+        sender: ActorId,
+        recipient: ActorId,
+        amount: u128,
+    ) -> Result<(), Error> {
+        let balance = self.ft_balances.entry(sender).or_default();
+
+        if *balance < amount {
+            return Err(Error::TokenTransferFailed);
+        }
+
+        *balance -= amount;
+        *self.ft_balances.entry(recipient).or_default() += amount;
+
+        Ok(())
+    }
+
+    /// Removes the soonest expiring reservation, from which a settlement is
+    /// sent.
+    fn take_reservation(&mut self) {
+        if let Some(index) = self
+            .reservations
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, (_, expiration))| *expiration)
+            .map(|(index, _)| index)
+        {
+            self.reservations.remove(index);
+        }
+    }
+}
+
+/// Whether a reply timeout set by [`Action::ConfigureTokenCalls`] is finite.
+fn is_finite(reply_timeout: Option<u32>) -> bool {
+    matches!(reply_timeout, Some(timeout) if timeout != 0)
+}
+
+/// Splits `amount` into `parts` equal prizes, the first of which also gets the
+/// remainder.
+fn split(amount: u128, parts: usize) -> Vec<u128> {
+    let part = amount / parts as u128;
+    let mut prizes = vec![part; parts];
+
+    prizes[0] += amount % parts as u128;
+
+    prizes
+}
@@ -0,0 +1,261 @@
+use gtest::System;
+use proptest::{collection, prelude::*};
+use utils::{prelude::*, FungibleToken};
+
+mod utils;
+
+const ADMIN: u64 = 3;
+const PLAYERS: [u64; 4] = [4, 5, 6, 7];
+const NATIVE_BALANCE: u128 = EXISTENTIAL_DEPOSIT * 1000;
+// Small balances make some transfers fail.
+const FT_BALANCES: [u128; PLAYERS.len()] = [
+    0,
+    EXISTENTIAL_DEPOSIT,
+    EXISTENTIAL_DEPOSIT * 3,
+    EXISTENTIAL_DEPOSIT * 100,
+];
+const MAX_NUMBER_OF_STEPS: usize = 32;
+
+#[derive(Debug, Clone, Copy)]
+enum Value {
+    Exact,
+    Zero,
+    Less,
+    More,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Start {
+        from_admin: bool,
+        blocks: u64,
+        cost_multiplier: u128,
+        in_tokens: bool,
+    },
+    Enter {
+        player: usize,
+        value: Value,
+    },
+    PickWinner {
+        from_admin: bool,
+    },
+    SpendBlocks(u32),
+}
+
+fn step() -> impl Strategy<Value = Step> {
+    let value = prop_oneof![
+        Just(Value::Exact),
+        Just(Value::Zero),
+        Just(Value::Less),
+        Just(Value::More),
+    ];
+
+    prop_oneof![
+        1 => (any::<bool>(), 1..4u64, 1..4u128, any::<bool>()).prop_map(
+            |(from_admin, blocks, cost_multiplier, in_tokens)| Step::Start {
+                from_admin,
+                blocks,
+                cost_multiplier,
+                in_tokens,
+            }
+        ),
+        4 => (0..PLAYERS.len(), value).prop_map(|(player, value)| Step::Enter { player, value }),
+        1 => any::<bool>().prop_map(|from_admin| Step::PickWinner { from_admin }),
+        1 => (1..4u32).prop_map(Step::SpendBlocks),
+    ]
+}
+
+struct Harness<'a> {
+    system: &'a System,
+    goc: Goc<'a>,
+    fungible_token: FungibleToken<'a>,
+    state: State,
+    ft_balances: [u128; PLAYERS.len()],
+    goc_ft_balance: u128,
+}
+
+impl<'a> Harness<'a> {
+    fn new(system: &'a System) -> Self {
+        let mut fungible_token = FungibleToken::initialize(system);
+        let goc = Goc::initialize_with_existential_deposit(system, ADMIN).succeed();
+
+        for (player, balance) in PLAYERS.into_iter().zip(FT_BALANCES) {
+            system.mint_to(player, NATIVE_BALANCE);
+
+            if balance != 0 {
+                fungible_token.mint(player, balance);
+            }
+
+            fungible_token.approve(player, goc.actor_id(), u128::MAX);
+        }
+
+        let state = goc.state().all().0;
+
+        Self {
+            system,
+            goc,
+            fungible_token,
+            state,
+            ft_balances: FT_BALANCES,
+            goc_ft_balance: 0,
+        }
+    }
+
+    fn native_balances(&self) -> u128 {
+        PLAYERS
+            .into_iter()
+            .chain([ADMIN])
+            .map(|actor| self.system.balance_of(actor))
+            .chain([self.system.balance_of(self.goc.actor_id().as_ref())])
+            .sum()
+    }
+
+    fn execute(&mut self, step: Step) {
+        let cost = self.state.participation_cost;
+        let result = match step {
+            Step::Start {
+                from_admin,
+                blocks,
+                cost_multiplier,
+                in_tokens,
+            } => {
+                self.goc
+                    .start(
+                        if from_admin { ADMIN } else { PLAYERS[0] },
+                        blocks * 1000,
+                        EXISTENTIAL_DEPOSIT * cost_multiplier,
+                        in_tokens.then(|| self.fungible_token.actor_id()),
+                    )
+                    .result
+            }
+            Step::Enter { player, value } => {
+                let value = match value {
+                    Value::Exact if self.state.fungible_token.is_some() => 0,
+                    Value::Exact => cost,
+                    Value::Zero => 0,
+                    Value::Less => cost.saturating_sub(EXISTENTIAL_DEPOSIT),
+                    Value::More => cost + EXISTENTIAL_DEPOSIT,
+                };
+
+                self.goc.enter_with_value(PLAYERS[player], value).result
+            }
+            Step::PickWinner { from_admin } => {
+                self.goc
+                    .pick_winner(if from_admin { ADMIN } else { PLAYERS[0] })
+                    .result
+            }
+            Step::SpendBlocks(blocks) => {
+                self.system.spend_blocks(blocks);
+
+                return self.check();
+            }
+        };
+
+        assert!(!result.main_failed());
+
+        for actor in PLAYERS.into_iter().chain([ADMIN]) {
+            self.system.claim_value_from_mailbox(actor);
+        }
+
+        self.check();
+    }
+
+    fn check(&mut self) {
+        let previous = mem::take(&mut self.state);
+        let state = self.goc.state().all().0;
+
+        // The prize fund always consists of participation costs.
+        assert_eq!(
+            state.prize_fund,
+            state.participation_cost * state.players.len() as u128
+        );
+
+        // No one participates twice.
+        let mut players = state.players.clone();
+
+        players.sort_unstable();
+        players.dedup();
+        assert_eq!(players.len(), state.players.len());
+
+        // Game rounds go through statuses in order.
+        assert!(
+            state.round == previous.round + 1 && state.status == RoundStatus::Entry
+                || state.round == previous.round
+                    && is_allowed_transition(previous.status, state.status),
+            "{:?} in round {} -> {:?} in round {}",
+            previous.status,
+            previous.round,
+            state.status,
+            state.round,
+        );
+
+        // The native value is never created or destroyed.
+        assert_eq!(
+            self.native_balances(),
+            NATIVE_BALANCE * PLAYERS.len() as u128 + EXISTENTIAL_DEPOSIT
+        );
+
+        // FT balances follow entries & payouts.
+        if state.round == previous.round && state.fungible_token.is_some() {
+            if let Some(player) = state.players.get(previous.players.len()) {
+                let index = PLAYERS
+                    .iter()
+                    .position(|&id| ActorId::from(id) == *player)
+                    .unwrap();
+
+                self.ft_balances[index] -= state.participation_cost;
+                self.goc_ft_balance += state.participation_cost;
+            }
+
+            if previous.status != RoundStatus::Finished && state.status == RoundStatus::Finished {
+                let winner = state.winner.unwrap();
+                let index = PLAYERS
+                    .iter()
+                    .position(|&id| ActorId::from(id) == winner)
+                    .unwrap();
+
+                self.ft_balances[index] += state.prize_fund;
+                self.goc_ft_balance -= state.prize_fund;
+            }
+        }
+
+        for (player, balance) in PLAYERS.into_iter().zip(self.ft_balances) {
+            self.fungible_token.balance(player).contains(balance);
+        }
+
+        self.fungible_token
+            .balance(self.goc.actor_id())
+            .contains(self.goc_ft_balance);
+
+        self.state = state;
+    }
+}
+
+fn is_allowed_transition(from: RoundStatus, to: RoundStatus) -> bool {
+    use RoundStatus::*;
+
+    from == to
+        || matches!(
+            (from, to),
+            // The entry stage can end while an action is being processed.
+            (Entry, AwaitingDraw | Finished | Cancelled) | (AwaitingDraw, Finished | Cancelled)
+        )
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        // Every case deploys the whole sharded FT stack.
+        cases: 32,
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn invariants(steps in collection::vec(step(), 1..MAX_NUMBER_OF_STEPS)) {
+        let system = utils::initialize_system();
+        let mut harness = Harness::new(&system);
+
+        for step in steps {
+            harness.execute(step);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Tests of [`TestAction::OverrideRandomness`], which is only available with
+//! the `testing` feature.
+
+#![cfg(feature = "testing")]
+
+use gtest::System;
+use utils::prelude::*;
+
+mod utils;
+
+const ADMIN: u64 = 3;
+const PLAYERS: [u64; 3] = [4, 5, 6];
+const PARTICIPATION_COST: u128 = 10000;
+const DURATION: u64 = 2000;
+const DURATION_IN_SECS: u32 = (DURATION / 1000) as _;
+
+fn play_round<'a>(system: &'a System, selection: Selection) -> Goc<'a> {
+    let mut goc = Goc::initialize(system, ADMIN).succeed();
+
+    let options = StartOptions {
+        selection,
+        ..Default::default()
+    };
+
+    goc.start_with(ADMIN, DURATION, PARTICIPATION_COST, None, options)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            None,
+            options,
+        ));
+
+    for player in PLAYERS {
+        system.mint_to(player, PARTICIPATION_COST);
+        goc.enter_with_value(player, PARTICIPATION_COST)
+            .succeed((1, player));
+    }
+
+    system.spend_blocks(DURATION_IN_SECS);
+
+    goc
+}
+
+#[test]
+fn forced_winner() {
+    let system = utils::initialize_system();
+    let mut goc = play_round(&system, Selection::Uniform);
+    let randomness_override = Some(RandomnessOverride::Winner(1));
+
+    goc.override_randomness(ADMIN, randomness_override)
+        .succeed(randomness_override);
+    goc.pick_winner(ADMIN)
+        .succeed((1, PLAYERS[1].into(), PARTICIPATION_COST * 3, 0));
+}
+
+#[test]
+fn fixed_seed() {
+    const SEED: u64 = 42;
+
+    let system = utils::initialize_system();
+    let selection = Selection::Multiple(2);
+    let mut goc = play_round(&system, selection);
+    let randomness_override = Some(RandomnessOverride::Seed(SEED));
+    let prize = PARTICIPATION_COST * 3 / 2;
+
+    goc.override_randomness(ADMIN, randomness_override)
+        .succeed(randomness_override);
+    goc.pick_winners(ADMIN).succeed((
+        1,
+        selection
+            .pick(SEED, &[PARTICIPATION_COST; 3])
+            .into_iter()
+            .map(|index| (PLAYERS[index].into(), prize))
+            .collect(),
+        0,
+    ));
+}
+
+#[test]
+fn override_by_not_admin() {
+    let system = utils::initialize_system();
+    let mut goc = play_round(&system, Selection::Uniform);
+
+    goc.override_randomness(PLAYERS[0], Some(RandomnessOverride::Seed(0)))
+        .failed(Error::AccessRestricted);
+}
+
+#[test]
+fn invalid_forced_winner() {
+    let system = utils::initialize_system();
+    let mut goc = play_round(&system, Selection::Uniform);
+
+    goc.override_randomness(ADMIN, Some(RandomnessOverride::Winner(3)))
+        .succeed(Some(RandomnessOverride::Winner(3)));
+    goc.pick_winner(ADMIN).failed(Error::InvalidSelection);
+
+    goc.override_randomness(ADMIN, None).succeed(None);
+    goc.pick_winner(ADMIN).succeed((
+        1,
+        utils::predict_winner(&system, &PLAYERS),
+        PARTICIPATION_COST * 3,
+        0,
+    ));
+}
+
+/// A winner can't be forced if a selection strategy picks several ones.
+#[test]
+fn forced_winner_of_multiple() {
+    let system = utils::initialize_system();
+    let mut goc = play_round(&system, Selection::Multiple(2));
+
+    goc.override_randomness(ADMIN, Some(RandomnessOverride::Winner(0)))
+        .succeed(Some(RandomnessOverride::Winner(0)));
+    goc.pick_winners(ADMIN).failed(Error::InvalidSelection);
+}
@@ -0,0 +1,317 @@
+//! Declarative end-to-end scenarios in `tests/scenarios/*.toml`.
+//!
+//! A scenario deploys the contract with `init`, optionally deploys the sharded
+//! FT contract if `fungible_token = true`, and runs `steps` in order stopping
+//! at the first failed one. Actors are named in `actors` by their IDs, and
+//! the contracts are named `game_of_chance` & `fungible_token`. Steps are
+//! tables with one of the keys:
+//! - `mint = { actor, value }` mints the native value to an actor;
+//! - `mint_tokens = { actor, amount }` mints FTs to an actor;
+//! - `approve = { actor, amount }` approves FTs of an actor to the contract;
+//! - `send = { from, action, value, expect }` sends an `Action` with `value`
+//! (0 by default) and checks its reply against `Result<Event, Error>`;
+//! - `wait = <blocks>` spends blocks;
+//! - `state = <State>` checks the contract state;
+//! - `balance = { actor, value }` claims the value from an actor's mailbox &
+//! checks its native balance;
+//! - `token_balance = { actor, amount }` checks an FT balance of an actor.
+//!
+//! See [`value`] for how contract types are written. Set `GOC_SCENARIO` to run
+//! only scenarios whose file names contain it.
+
+use game_of_chance_client::{gtest::GtestTransport, Transport};
+use game_of_chance_io::*;
+use gstd::ActorId;
+use gtest::{Program, System};
+use serde::Deserialize;
+use std::{collections::BTreeMap, env, fs, path::Path};
+use toml::Value;
+use utils::{common::Program as _, FungibleToken, FOREIGN_USER};
+use value::{Context, Types};
+
+mod utils;
+#[path = "scenarios/value.rs"]
+mod value;
+
+const SCENARIOS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/scenarios");
+const GAME_OF_CHANCE: &str = "game_of_chance";
+const FUNGIBLE_TOKEN: &str = "fungible_token";
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Scenario {
+    actors: BTreeMap<String, u64>,
+    #[serde(default)]
+    fungible_token: bool,
+    init: Value,
+    steps: Vec<Step>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum Step {
+    Mint {
+        actor: String,
+        value: Value,
+    },
+    MintTokens {
+        actor: String,
+        amount: Value,
+    },
+    Approve {
+        actor: String,
+        amount: Value,
+    },
+    Send {
+        from: String,
+        action: Value,
+        value: Option<Value>,
+        expect: Value,
+    },
+    Wait(u32),
+    State(Value),
+    Balance {
+        actor: String,
+        value: Value,
+    },
+    TokenBalance {
+        actor: String,
+        amount: Value,
+    },
+}
+
+struct Runner<'a> {
+    system: &'a System,
+    types: &'a Types,
+    users: &'a BTreeMap<String, u64>,
+    actors: BTreeMap<String, ActorId>,
+    transport: GtestTransport<'a>,
+    fungible_token: Option<FungibleToken<'a>>,
+}
+
+impl<'a> Runner<'a> {
+    fn context(&self) -> Context {
+        Context {
+            actors: &self.actors,
+            now: self.system.block_timestamp(),
+        }
+    }
+
+    fn integer(&self, value: &Value) -> Result<u128, String> {
+        self.context()
+            .integer(value)
+            .map_err(|error| error.to_string())
+    }
+
+    fn user(&self, name: &str) -> Result<u64, String> {
+        self.users
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!("unknown actor `{name}`"))
+    }
+
+    fn actor(&self, name: &str) -> Result<ActorId, String> {
+        self.actors
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!("unknown actor `{name}`"))
+    }
+
+    fn fungible_token(&mut self) -> Result<&mut FungibleToken<'a>, String> {
+        self.fungible_token
+            .as_mut()
+            .ok_or_else(|| "the scenario has no FT contract".into())
+    }
+
+    async fn run(&mut self, step: &Step) -> Result<(), String> {
+        match step {
+            Step::Mint { actor, value } => {
+                let (actor, value) = (self.actor(actor)?, self.integer(value)?);
+
+                self.system.mint_to(actor.as_ref(), value);
+            }
+            Step::MintTokens { actor, amount } => {
+                let (user, amount) = (self.user(actor)?, self.integer(amount)?);
+
+                self.fungible_token()?.mint(user, amount);
+            }
+            Step::Approve { actor, amount } => {
+                let (user, amount) = (self.user(actor)?, self.integer(amount)?);
+                let game_of_chance = self.actor(GAME_OF_CHANCE)?;
+
+                self.fungible_token()?.approve(user, game_of_chance, amount);
+            }
+            Step::Send {
+                from,
+                action,
+                value,
+                expect,
+            } => {
+                let context = self.context();
+                let action: Action = self
+                    .types
+                    .parse(self.types.action, action, &context)
+                    .map_err(|error| error.at("action").to_string())?;
+                let from = self.actor(from)?;
+                let value = value.as_ref().map_or(Ok(0), |value| self.integer(value))?;
+
+                self.transport.set_sender(from);
+
+                let reply = self
+                    .transport
+                    .send(action, value)
+                    .await
+                    .map_err(|error| error.to_string())?;
+
+                self.check(self.types.reply, expect, &reply, "expect")?;
+            }
+            Step::Wait(blocks) => self.system.spend_blocks(*blocks),
+            Step::State(expected) => {
+                let state = self
+                    .transport
+                    .state()
+                    .await
+                    .map_err(|error| error.to_string())?;
+
+                self.check(self.types.state, expected, &state, "state")?;
+            }
+            Step::Balance { actor, value } => {
+                let (actor, value) = (self.actor(actor)?, self.integer(value)?);
+
+                self.system.claim_value_from_mailbox(actor.as_ref());
+
+                let balance = self.system.balance_of(actor.as_ref());
+
+                if balance != value {
+                    return Err(format!("expected the balance {value}, found {balance}"));
+                }
+            }
+            Step::TokenBalance { actor, amount } => {
+                let (actor, amount) = (self.actor(actor)?, self.integer(amount)?);
+                let balance = self.fungible_token()?.balance_of(actor);
+
+                if balance != amount {
+                    return Err(format!("expected the FT balance {amount}, found {balance}"));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check(
+        &self,
+        id: u32,
+        expected: &Value,
+        actual: &impl parity_scale_codec::Encode,
+        key: &str,
+    ) -> Result<(), String> {
+        let context = self.context();
+        let actual = self
+            .types
+            .render(id, actual, &context)
+            .map_err(|error| error.to_string())?;
+
+        value::check(expected, &actual, &context)
+            .map_err(|error| format!("{}\nthe actual value: {actual}", error.at(key)))
+    }
+}
+
+async fn run(types: &Types, scenario: &Scenario) -> Result<(), String> {
+    let system = utils::initialize_system();
+    let program = Program::current(&system);
+    let program_id: [u8; 32] = program.id().into();
+    let fungible_token = scenario
+        .fungible_token
+        .then(|| FungibleToken::initialize(&system));
+    let mut actors: BTreeMap<_, _> = scenario
+        .actors
+        .iter()
+        .map(|(name, id)| (name.clone(), ActorId::from(*id)))
+        .collect();
+
+    actors.insert(GAME_OF_CHANCE.into(), program_id.into());
+
+    if let Some(fungible_token) = &fungible_token {
+        actors.insert(FUNGIBLE_TOKEN.into(), fungible_token.actor_id());
+    }
+
+    let context = Context {
+        actors: &actors,
+        now: system.block_timestamp(),
+    };
+    let initialize: Initialize = types
+        .parse(types.initialize, &scenario.init, &context)
+        .map_err(|error| error.at("init").to_string())?;
+
+    if program.send(FOREIGN_USER, initialize).main_failed() {
+        return Err("failed to initialize the contract".into());
+    }
+
+    let mut runner = Runner {
+        system: &system,
+        types,
+        users: &scenario.actors,
+        actors,
+        transport: GtestTransport::new(&system, program, FOREIGN_USER),
+        fungible_token,
+    };
+
+    for (index, step) in scenario.steps.iter().enumerate() {
+        runner
+            .run(step)
+            .await
+            .map_err(|error| format!("step {}: {error}", index + 1))?;
+    }
+
+    Ok(())
+}
+
+fn load(path: &Path) -> Result<Scenario, String> {
+    let scenario = fs::read_to_string(path).map_err(|error| error.to_string())?;
+
+    toml::from_str(&scenario).map_err(|error| error.to_string())
+}
+
+#[tokio::test]
+async fn scenarios() {
+    let filter = env::var("GOC_SCENARIO").unwrap_or_default();
+    let types = Types::new();
+    let mut paths: Vec<_> = fs::read_dir(SCENARIOS)
+        .expect("failed to read scenarios")
+        .map(|entry| entry.expect("failed to read scenarios").path())
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == "toml")
+                && path.file_stem().map_or(false, |name| {
+                    name.to_string_lossy().contains(filter.as_str())
+                })
+        })
+        .collect();
+
+    paths.sort();
+
+    assert!(!paths.is_empty(), "no scenarios found");
+
+    let mut failures = vec![];
+
+    for path in paths {
+        let name = path.file_name().expect("a file path").to_string_lossy();
+        let result = match load(&path) {
+            Ok(scenario) => run(&types, &scenario).await,
+            Err(error) => Err(error),
+        };
+
+        match result {
+            Ok(()) => println!("{name}: passed"),
+            Err(error) => failures.push(format!("{name}: {error}")),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} scenario(s) failed:\n{}",
+        failures.len(),
+        failures.join("\n\n")
+    );
+}
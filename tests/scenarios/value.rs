@@ -0,0 +1,551 @@
+//! Conversions between TOML values & SCALE encodings of the contract types.
+//!
+//! Types are resolved from [`ContractMetadata`], so scenarios follow the ABI
+//! without changes in the runner. Values are mapped as follows:
+//! - structs are tables, and tuples, arrays & sequences are arrays;
+//! - unit enum variants are strings, and other variants are tables with the
+//! only key, a variant name, whose value is a table of named fields, the only
+//! unnamed field, or an array of unnamed fields;
+//! - `Option`s are `"None"` or their inner values, and missing struct fields
+//! are `None`s;
+//! - `ActorId`s are actor names;
+//! - integers are TOML integers, or strings if they don't fit in `i64`.
+//!
+//! Integers can also be `"now"` or `"now+<ms>"` relative to the block timestamp
+//! at the time of a step, and any expected value can be `"*"` to skip it.
+
+use game_of_chance_io::ContractMetadata;
+use gmeta::Metadata;
+use gstd::ActorId;
+use parity_scale_codec::{Compact, Decode, Encode};
+use scale_info::{form::PortableForm, Field, PortableRegistry, Type, TypeDef, TypeDefPrimitive};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+};
+use toml::{value::Table, Value};
+
+/// A conversion error or a mismatch of values at `path`.
+#[derive(Debug)]
+pub struct Error {
+    path: Vec<String>,
+    message: String,
+}
+
+impl Error {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            path: vec![],
+            message: message.into(),
+        }
+    }
+
+    pub fn at(mut self, segment: impl Display) -> Self {
+        self.path.push(segment.to_string());
+
+        self
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if !self.path.is_empty() {
+            let path: Vec<_> = self.path.iter().rev().map(String::as_str).collect();
+
+            write!(f, "`{}`: ", path.join("."))?;
+        }
+
+        f.write_str(&self.message)
+    }
+}
+
+impl From<parity_scale_codec::Error> for Error {
+    fn from(error: parity_scale_codec::Error) -> Self {
+        Self::new(format!("failed to decode: {error}"))
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Names of actors & the block timestamp at the time of a step.
+pub struct Context<'a> {
+    pub actors: &'a BTreeMap<String, ActorId>,
+    pub now: u64,
+}
+
+impl Context<'_> {
+    fn actor(&self, value: &Value) -> Result<ActorId> {
+        let name = value
+            .as_str()
+            .ok_or_else(|| Error::new(format!("expected an actor name, found `{value}`")))?;
+
+        self.actors
+            .get(name)
+            .copied()
+            .ok_or_else(|| Error::new(format!("unknown actor `{name}`")))
+    }
+
+    fn name(&self, actor: ActorId) -> String {
+        self.actors
+            .iter()
+            .find(|(_, id)| **id == actor)
+            .map_or_else(
+                || format!("0x{}", hex::encode(actor)),
+                |(name, _)| name.clone(),
+            )
+    }
+
+    pub fn integer(&self, value: &Value) -> Result<u128> {
+        let error = || Error::new(format!("expected an integer, found `{value}`"));
+
+        match value {
+            Value::Integer(integer) => (*integer).try_into().map_err(|_| error()),
+            Value::String(string) => match string.strip_prefix("now") {
+                Some("") => Ok(self.now.into()),
+                Some(offset) => offset
+                    .trim_start()
+                    .strip_prefix('+')
+                    .and_then(|offset| offset.trim().parse::<u64>().ok())
+                    .map(|offset| (self.now + offset).into())
+                    .ok_or_else(error),
+                None => string.parse().map_err(|_| error()),
+            },
+            _ => Err(error()),
+        }
+    }
+}
+
+/// The contract types from [`ContractMetadata`].
+pub struct Types {
+    registry: PortableRegistry,
+    pub initialize: u32,
+    pub action: u32,
+    pub reply: u32,
+    pub state: u32,
+}
+
+impl Types {
+    pub fn new() -> Self {
+        let repr = ContractMetadata::repr();
+        let id = |id: Option<u32>| id.expect("the metadata is incomplete");
+
+        Self {
+            registry: PortableRegistry::decode(&mut repr.registry.as_slice())
+                .expect("failed to decode the registry"),
+            initialize: id(repr.init.input),
+            action: id(repr.handle.input),
+            reply: id(repr.handle.output),
+            state: id(repr.state),
+        }
+    }
+
+    /// Converts `value` to `T` described by the type with `id`.
+    pub fn parse<T: Decode>(&self, id: u32, value: &Value, context: &Context) -> Result<T> {
+        let mut encoded = vec![];
+
+        self.encode(id, value, context, &mut encoded)?;
+
+        Ok(T::decode(&mut encoded.as_slice())?)
+    }
+
+    /// Converts `value` described by the type with `id` to a TOML value.
+    pub fn render(&self, id: u32, value: &impl Encode, context: &Context) -> Result<Value> {
+        let encoded = value.encode();
+        let mut input = encoded.as_slice();
+        let value = self.decode(id, &mut input, context)?;
+
+        if !input.is_empty() {
+            return Err(Error::new("the value is longer than its type"));
+        }
+
+        Ok(value)
+    }
+
+    fn resolve(&self, id: u32) -> &Type<PortableForm> {
+        self.registry
+            .resolve(id)
+            .expect("the registry is incomplete")
+    }
+
+    /// An inner type of `Option` if the type with `id` is it.
+    fn option(&self, id: u32) -> Option<u32> {
+        let ty = self.resolve(id);
+
+        match &ty.type_def {
+            TypeDef::Variant(variant)
+                if ty.path.segments.last().map(String::as_str) == Some("Option") =>
+            {
+                variant
+                    .variants
+                    .iter()
+                    .find(|variant| variant.name == "Some")
+                    .and_then(|variant| variant.fields.first())
+                    .map(|field| field.ty.id)
+            }
+            _ => None,
+        }
+    }
+
+    fn encode(
+        &self,
+        id: u32,
+        value: &Value,
+        context: &Context,
+        output: &mut Vec<u8>,
+    ) -> Result<()> {
+        let ty = self.resolve(id);
+
+        if ty.path.segments.last().map(String::as_str) == Some("ActorId") {
+            context.actor(value)?.encode_to(output);
+
+            return Ok(());
+        }
+
+        if let Some(inner) = self.option(id) {
+            if value.as_str() == Some("None") {
+                output.push(0);
+            } else {
+                output.push(1);
+                self.encode(inner, value, context, output)?;
+            }
+
+            return Ok(());
+        }
+
+        match &ty.type_def {
+            TypeDef::Composite(composite) => {
+                self.encode_fields(&composite.fields, value, context, output)?
+            }
+            TypeDef::Variant(variant) => {
+                let (name, fields) = match value {
+                    Value::String(name) => (name, None),
+                    Value::Table(table) if table.len() == 1 => {
+                        let (name, fields) = table.iter().next().expect("the table isn't empty");
+
+                        (name, Some(fields))
+                    }
+                    _ => {
+                        return Err(Error::new(format!(
+                            "expected a variant name or a table with it, found `{value}`"
+                        )))
+                    }
+                };
+                let variant = variant
+                    .variants
+                    .iter()
+                    .find(|variant| &variant.name == name)
+                    .ok_or_else(|| Error::new(format!("unknown variant `{name}`")))?;
+
+                output.push(variant.index);
+
+                match fields {
+                    Some(fields) => self
+                        .encode_fields(&variant.fields, fields, context, output)
+                        .map_err(|error| error.at(name))?,
+                    None if variant.fields.is_empty() => {}
+                    None => return Err(Error::new(format!("`{name}` has fields"))),
+                }
+            }
+            TypeDef::Sequence(sequence) => {
+                let items = array(value)?;
+
+                Compact(items.len() as u32).encode_to(output);
+
+                for (index, item) in items.iter().enumerate() {
+                    self.encode(sequence.type_param.id, item, context, output)
+                        .map_err(|error| error.at(index))?;
+                }
+            }
+            TypeDef::Array(array_def) => {
+                let ids = vec![array_def.type_param.id; array_def.len as usize];
+
+                self.encode_items(&ids, value, context, output)?;
+            }
+            TypeDef::Tuple(tuple) => {
+                let ids: Vec<_> = tuple.fields.iter().map(|ty| ty.id).collect();
+
+                self.encode_items(&ids, value, context, output)?;
+            }
+            TypeDef::Primitive(primitive) => encode_primitive(primitive, value, context, output)?,
+            TypeDef::Compact(_) => Compact(context.integer(value)?).encode_to(output),
+            TypeDef::BitSequence(_) => return Err(Error::new("bit sequences aren't supported")),
+        }
+
+        Ok(())
+    }
+
+    fn encode_fields(
+        &self,
+        fields: &[Field<PortableForm>],
+        value: &Value,
+        context: &Context,
+        output: &mut Vec<u8>,
+    ) -> Result<()> {
+        match fields {
+            [] => Ok(()),
+            [field] if field.name.is_none() => self.encode(field.ty.id, value, context, output),
+            [field, ..] if field.name.is_none() => {
+                let ids: Vec<_> = fields.iter().map(|field| field.ty.id).collect();
+
+                self.encode_items(&ids, value, context, output)
+            }
+            _ => {
+                let table = value
+                    .as_table()
+                    .ok_or_else(|| Error::new(format!("expected a table, found `{value}`")))?;
+
+                if let Some(key) = table
+                    .keys()
+                    .find(|key| !fields.iter().any(|field| field.name.as_ref() == Some(*key)))
+                {
+                    return Err(Error::new(format!("unknown field `{key}`")));
+                }
+
+                for field in fields {
+                    let name = field.name.as_ref().expect("all fields are named");
+
+                    match table.get(name) {
+                        Some(value) => self
+                            .encode(field.ty.id, value, context, output)
+                            .map_err(|error| error.at(name))?,
+                        None if self.option(field.ty.id).is_some() => output.push(0),
+                        None => return Err(Error::new(format!("missing field `{name}`"))),
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    fn encode_items(
+        &self,
+        ids: &[u32],
+        value: &Value,
+        context: &Context,
+        output: &mut Vec<u8>,
+    ) -> Result<()> {
+        let items = array(value)?;
+
+        if items.len() != ids.len() {
+            return Err(Error::new(format!(
+                "expected {} items, found {}",
+                ids.len(),
+                items.len()
+            )));
+        }
+
+        for (index, (id, item)) in ids.iter().zip(items).enumerate() {
+            self.encode(*id, item, context, output)
+                .map_err(|error| error.at(index))?;
+        }
+
+        Ok(())
+    }
+
+    fn decode(&self, id: u32, input: &mut &[u8], context: &Context) -> Result<Value> {
+        let ty = self.resolve(id);
+
+        if ty.path.segments.last().map(String::as_str) == Some("ActorId") {
+            return Ok(Value::String(context.name(ActorId::decode(input)?)));
+        }
+
+        if let Some(inner) = self.option(id) {
+            return match u8::decode(input)? {
+                0 => Ok(Value::String("None".into())),
+                1 => self.decode(inner, input, context),
+                _ => Err(Error::new("invalid `Option`")),
+            };
+        }
+
+        Ok(match &ty.type_def {
+            TypeDef::Composite(composite) => {
+                self.decode_fields(&composite.fields, input, context)?
+            }
+            TypeDef::Variant(variant) => {
+                let index = u8::decode(input)?;
+                let variant = variant
+                    .variants
+                    .iter()
+                    .find(|variant| variant.index == index)
+                    .ok_or_else(|| Error::new(format!("unknown variant index {index}")))?;
+
+                if variant.fields.is_empty() {
+                    Value::String(variant.name.clone())
+                } else {
+                    let fields = self
+                        .decode_fields(&variant.fields, input, context)
+                        .map_err(|error| error.at(&variant.name))?;
+
+                    Value::Table(Table::from_iter([(variant.name.clone(), fields)]))
+                }
+            }
+            TypeDef::Sequence(sequence) => {
+                let len = Compact::<u32>::decode(input)?.0;
+                let ids = vec![sequence.type_param.id; len as usize];
+
+                self.decode_items(&ids, input, context)?
+            }
+            TypeDef::Array(array_def) => {
+                let ids = vec![array_def.type_param.id; array_def.len as usize];
+
+                self.decode_items(&ids, input, context)?
+            }
+            TypeDef::Tuple(tuple) => {
+                let ids: Vec<_> = tuple.fields.iter().map(|ty| ty.id).collect();
+
+                self.decode_items(&ids, input, context)?
+            }
+            TypeDef::Primitive(primitive) => decode_primitive(primitive, input)?,
+            TypeDef::Compact(_) => integer(Compact::<u128>::decode(input)?.0),
+            TypeDef::BitSequence(_) => return Err(Error::new("bit sequences aren't supported")),
+        })
+    }
+
+    fn decode_fields(
+        &self,
+        fields: &[Field<PortableForm>],
+        input: &mut &[u8],
+        context: &Context,
+    ) -> Result<Value> {
+        match fields {
+            [] => Ok(Value::Table(Table::new())),
+            [field] if field.name.is_none() => self.decode(field.ty.id, input, context),
+            [field, ..] if field.name.is_none() => {
+                let ids: Vec<_> = fields.iter().map(|field| field.ty.id).collect();
+
+                self.decode_items(&ids, input, context)
+            }
+            _ => {
+                let mut table = Table::new();
+
+                for field in fields {
+                    let name = field.name.clone().expect("all fields are named");
+                    let value = self
+                        .decode(field.ty.id, input, context)
+                        .map_err(|error| error.at(&name))?;
+
+                    table.insert(name, value);
+                }
+
+                Ok(Value::Table(table))
+            }
+        }
+    }
+
+    fn decode_items(&self, ids: &[u32], input: &mut &[u8], context: &Context) -> Result<Value> {
+        ids.iter()
+            .enumerate()
+            .map(|(index, id)| {
+                self.decode(*id, input, context)
+                    .map_err(|error| error.at(index))
+            })
+            .collect::<Result<_>>()
+            .map(Value::Array)
+    }
+}
+
+fn array(value: &Value) -> Result<&Vec<Value>> {
+    value
+        .as_array()
+        .ok_or_else(|| Error::new(format!("expected an array, found `{value}`")))
+}
+
+fn integer(integer: u128) -> Value {
+    i64::try_from(integer).map_or_else(|_| Value::String(integer.to_string()), Value::Integer)
+}
+
+fn encode_primitive(
+    primitive: &TypeDefPrimitive,
+    value: &Value,
+    context: &Context,
+    output: &mut Vec<u8>,
+) -> Result<()> {
+    fn narrow<T: TryFrom<u128> + Encode>(integer: u128, output: &mut Vec<u8>) -> Result<()> {
+        T::try_from(integer)
+            .map_err(|_| Error::new(format!("{integer} is out of range")))?
+            .encode_to(output);
+
+        Ok(())
+    }
+
+    match primitive {
+        TypeDefPrimitive::Bool => value
+            .as_bool()
+            .ok_or_else(|| Error::new(format!("expected a boolean, found `{value}`")))?
+            .encode_to(output),
+        TypeDefPrimitive::Str => value
+            .as_str()
+            .ok_or_else(|| Error::new(format!("expected a string, found `{value}`")))?
+            .encode_to(output),
+        TypeDefPrimitive::U8 => narrow::<u8>(context.integer(value)?, output)?,
+        TypeDefPrimitive::U16 => narrow::<u16>(context.integer(value)?, output)?,
+        TypeDefPrimitive::U32 => narrow::<u32>(context.integer(value)?, output)?,
+        TypeDefPrimitive::U64 => narrow::<u64>(context.integer(value)?, output)?,
+        TypeDefPrimitive::U128 => context.integer(value)?.encode_to(output),
+        _ => return Err(Error::new(format!("`{primitive:?}` isn't supported"))),
+    }
+
+    Ok(())
+}
+
+fn decode_primitive(primitive: &TypeDefPrimitive, input: &mut &[u8]) -> Result<Value> {
+    Ok(match primitive {
+        TypeDefPrimitive::Bool => Value::Boolean(bool::decode(input)?),
+        TypeDefPrimitive::Str => Value::String(String::decode(input)?),
+        TypeDefPrimitive::U8 => integer(u8::decode(input)?.into()),
+        TypeDefPrimitive::U16 => integer(u16::decode(input)?.into()),
+        TypeDefPrimitive::U32 => integer(u32::decode(input)?.into()),
+        TypeDefPrimitive::U64 => integer(u64::decode(input)?.into()),
+        TypeDefPrimitive::U128 => integer(u128::decode(input)?),
+        _ => return Err(Error::new(format!("`{primitive:?}` isn't supported"))),
+    })
+}
+
+/// Checks that `actual` matches `expected`. Tables match if all keys of
+/// `expected` match, so expectations may omit irrelevant fields.
+pub fn check(expected: &Value, actual: &Value, context: &Context) -> Result<()> {
+    match (expected, actual) {
+        (Value::String(any), _) if any == "*" => Ok(()),
+        (Value::Table(expected), Value::Table(actual_table)) => {
+            for (key, expected) in expected {
+                let actual = actual_table
+                    .get(key)
+                    .ok_or_else(|| Error::new(format!("expected `{key}`, found `{actual}`")))?;
+
+                check(expected, actual, context).map_err(|error| error.at(key))?;
+            }
+
+            Ok(())
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            if expected.len() != actual.len() {
+                return Err(Error::new(format!(
+                    "expected {} items, found {}",
+                    expected.len(),
+                    actual.len()
+                )));
+            }
+
+            for (index, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                check(expected, actual, context).map_err(|error| error.at(index))?;
+            }
+
+            Ok(())
+        }
+        _ => {
+            let is_equal = match (context.integer(expected), context.integer(actual)) {
+                (Ok(expected), Ok(actual)) => expected == actual,
+                _ => expected == actual,
+            };
+
+            if is_equal {
+                Ok(())
+            } else {
+                Err(Error::new(format!(
+                    "expected `{expected}`, found `{actual}`"
+                )))
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use fmt::Debug;
 use ft_main_io::{FTokenAction, FTokenEvent, InitFToken, LogicAction};
 use game_of_chance::WASM_BINARY_OPT;
+use game_of_chance_client::{gclient::GclientTransport, Client, ClientError, Transport};
 use game_of_chance_io::*;
 use gclient::{Error as GclientError, EventListener, EventProcessor, GearApi, Result};
 use gstd::prelude::*;
@@ -11,6 +12,13 @@ const ALICE: [u8; 32] = [
     76, 205, 227, 154, 86, 132, 231, 165, 109, 162, 125,
 ];
 
+/// See `wasm_path()` in `tests/utils/common.rs`.
+fn wasm_path(name: &str) -> String {
+    let target_dir = option_env!("CARGO_TARGET_DIR").unwrap_or("target");
+
+    format!("{target_dir}/wasm32-unknown-unknown/debug/{name}.opt.wasm")
+}
+
 fn decode<T: Decode>(payload: Vec<u8>) -> Result<T> {
     Ok(T::decode(&mut payload.as_slice())?)
 }
@@ -132,15 +140,6 @@ async fn send_message<T: Decode>(
     )
 }
 
-async fn send_message_for_goc(
-    client: &GearApi,
-    listener: &mut EventListener,
-    destination: [u8; 32],
-    payload: impl Encode + Debug,
-) -> Result<Result<Event, Error>> {
-    send_message(client, listener, destination, payload).await
-}
-
 async fn send_message_with_insufficient_gas(
     client: &GearApi,
     listener: &mut EventListener,
@@ -158,25 +157,17 @@ async fn send_message_with_insufficient_gas(
 
 #[tokio::test]
 #[ignore]
-async fn state_consistency() -> Result<()> {
+async fn state_consistency() -> std::result::Result<(), Box<dyn std::error::Error>> {
     let client = GearApi::dev_from_path(env!("GEAR_NODE_PATH")).await?;
     let mut listener = client.subscribe().await?;
 
-    let storage_code_hash = upload_code(
-        &client,
-        "target/wasm32-unknown-unknown/debug/ft_storage.opt.wasm",
-    )
-    .await?;
-    let ft_logic_code_hash = upload_code(
-        &client,
-        "target/wasm32-unknown-unknown/debug/ft_logic.opt.wasm",
-    )
-    .await?;
+    let storage_code_hash = upload_code(&client, &wasm_path("ft_storage")).await?;
+    let ft_logic_code_hash = upload_code(&client, &wasm_path("ft_logic")).await?;
 
     let ft_actor_id = upload_program(
         &client,
         &mut listener,
-        "target/wasm32-unknown-unknown/debug/ft_main.opt.wasm",
+        &wasm_path("ft_main"),
         InitFToken {
             storage_code_hash,
             ft_logic_code_hash,
@@ -190,6 +181,9 @@ async fn state_consistency() -> Result<()> {
         WASM_BINARY_OPT.into(),
         Initialize {
             admin: ALICE.into(),
+            // Only a game round in fungible tokens is played here, so the
+            // existential deposit doesn't matter.
+            existential_deposit: 0,
         },
     )
     .await?;
@@ -230,47 +224,72 @@ async fn state_consistency() -> Result<()> {
             .await?
     );
 
+    let mut goc = Client::new(GclientTransport::new(client.clone(), goc_actor_id).await?);
+
+    let started = goc
+        .start(
+            17000,
+            10000,
+            Some(ft_actor_id.into()),
+            Selection::Uniform,
+            None,
+            Pricing::Fixed,
+        )
+        .await?;
+
+    println!("{started:?}");
+
+    // The ending is set from the timestamp of the block where the contract
+    // replied.
+    let Event::Started { ending, .. } = started else {
+        panic!("unexpected reply: {started:?}");
+    };
+
+    assert_eq!(goc.transport().block_timestamp().await?, ending - 17000);
+
     println!(
-        "{:?}",
-        send_message_for_goc(
+        "{}",
+        send_message_with_insufficient_gas(
             &client,
             &mut listener,
             goc_actor_id,
-            Action::Start {
-                duration: 17000,
-                participation_cost: 10000,
-                fungible_token: Some(ft_actor_id.into())
-            }
+            Action::Enter(Some(1))
         )
         .await?
     );
-
-    let mut payload = Action::Enter;
-
-    println!(
-        "{}",
-        send_message_with_insufficient_gas(&client, &mut listener, goc_actor_id, payload).await?
-    );
     assert_eq!(
-        send_message_for_goc(&client, &mut listener, goc_actor_id, payload).await?,
-        Ok(Event::PlayerAdded(ALICE.into()))
+        goc.enter(Some(1), 0).await?,
+        Event::PlayerAdded {
+            round: 1,
+            player: ALICE.into()
+        }
     );
 
-    payload = Action::PickWinner;
-
     println!(
         "{}",
-        send_message_with_insufficient_gas(&client, &mut listener, goc_actor_id, payload).await?
+        send_message_with_insufficient_gas(
+            &client,
+            &mut listener,
+            goc_actor_id,
+            Action::PickWinner(Some(1))
+        )
+        .await?
     );
     assert_eq!(
-        Ok(Event::Winner(ALICE.into())),
-        send_message_for_goc(&client, &mut listener, goc_actor_id, payload).await?
+        Event::Winner {
+            round: 1,
+            winner: ALICE.into(),
+            prize: 10000,
+            dust: 0
+        },
+        goc.pick_winner(Some(1)).await?
     );
+    assert!(matches!(
+        goc.pick_winner(Some(1)).await,
+        Err(ClientError::Contract(Error::WinnerAlreadyPicked { .. }))
+    ));
 
-    println!(
-        "{:?}",
-        client.read_state::<State>(goc_actor_id.into()).await?
-    );
+    println!("{:?}", goc.state().await?);
 
     Ok(())
 }
@@ -1,4 +1,5 @@
-use utils::{prelude::*, FungibleToken};
+use game_of_chance_mock_ft::{Behaviour, Transfer};
+use utils::{prelude::*, FungibleToken, MockFungibleToken};
 
 mod utils;
 
@@ -20,6 +21,8 @@ fn two_rounds_and_meta_state() {
 
     goc.state().all().eq(State {
         admin,
+        existential_deposit: EXISTENTIAL_DEPOSIT,
+        token_reply_timeout: Some(DEFAULT_TOKEN_REPLY_TIMEOUT),
         ..Default::default()
     });
 
@@ -31,38 +34,50 @@ fn two_rounds_and_meta_state() {
     let mut started = system.block_timestamp();
     let mut ending = started + DURATION;
     let ft_actor_id = Some(fungible_token.actor_id());
-    let is_active = true;
+    let status = RoundStatus::Entry;
 
     goc.start(ADMIN, DURATION, PARTICIPATION_COST, ft_actor_id)
-        .succeed((ending, PARTICIPATION_COST, ft_actor_id));
+        .succeed((1, ending, PARTICIPATION_COST, ft_actor_id));
     goc.state().all().eq(State {
         admin,
+        existential_deposit: EXISTENTIAL_DEPOSIT,
         started,
         ending,
+        round: 1,
         participation_cost: PARTICIPATION_COST,
         fungible_token: ft_actor_id,
-        is_active,
+        status,
+        reserved_gas: SETTLEMENT_GAS,
+        token_reply_timeout: Some(DEFAULT_TOKEN_REPLY_TIMEOUT),
         ..Default::default()
     });
 
     let mut players = vec![];
+    let mut player_stats = BTreeMap::new();
 
     for (index, player) in PLAYERS.into_iter().enumerate() {
         let prize_fund = PARTICIPATION_COST * (index + 1) as u128;
 
         players.push(player.into());
+        update_stats(&mut player_stats, player, 1, ft_actor_id, None);
 
-        goc.enter(player).succeed(player);
+        goc.enter(player).succeed((1, player));
         fungible_token.balance(goc.actor_id()).contains(prize_fund);
         goc.state().all().eq(State {
             admin,
+            existential_deposit: EXISTENTIAL_DEPOSIT,
             started,
             ending,
+            round: 1,
             players: players.clone(),
+            stakes: vec![PARTICIPATION_COST; index + 1],
             prize_fund,
             participation_cost: PARTICIPATION_COST,
             fungible_token: ft_actor_id,
-            is_active,
+            status,
+            reserved_gas: SETTLEMENT_GAS,
+            player_stats: player_stats.clone().into_iter().collect(),
+            token_reply_timeout: Some(DEFAULT_TOKEN_REPLY_TIMEOUT),
             ..Default::default()
         });
     }
@@ -71,19 +86,37 @@ fn two_rounds_and_meta_state() {
 
     let winner = utils::predict_winner(&system, &PLAYERS);
 
-    goc.pick_winner(ADMIN).succeed(winner);
+    goc.pick_winner(ADMIN)
+        .succeed((1, winner, PARTICIPATION_COST * 3, 0));
+    update_stats(
+        &mut player_stats,
+        winner,
+        1,
+        ft_actor_id,
+        Some(PARTICIPATION_COST * 3),
+    );
+
+    let mut leaderboards = vec![(ft_actor_id, vec![(winner, PARTICIPATION_COST * 3)])];
     fungible_token
         .balance(winner)
         .contains(PARTICIPATION_COST * 2 + AMOUNT);
     goc.state().all().eq(State {
         admin,
+        existential_deposit: EXISTENTIAL_DEPOSIT,
         started,
         ending,
+        round: 1,
         players: players.clone(),
+        stakes: vec![PARTICIPATION_COST; 3],
         prize_fund: PARTICIPATION_COST * 3,
         participation_cost: PARTICIPATION_COST,
-        winner,
+        winner: Some(winner),
+        winners: vec![winner],
         fungible_token: ft_actor_id,
+        status: RoundStatus::Finished,
+        player_stats: player_stats.clone().into_iter().collect(),
+        leaderboards: leaderboards.clone(),
+        token_reply_timeout: Some(DEFAULT_TOKEN_REPLY_TIMEOUT),
         ..Default::default()
     });
 
@@ -95,13 +128,19 @@ fn two_rounds_and_meta_state() {
     ending = started + DURATION;
 
     goc.start(ADMIN, DURATION, PARTICIPATION_COST, None)
-        .succeed((ending, PARTICIPATION_COST, None));
+        .succeed((2, ending, PARTICIPATION_COST, None));
     goc.state().all().eq(State {
         admin,
+        existential_deposit: EXISTENTIAL_DEPOSIT,
         started,
         ending,
+        round: 2,
         participation_cost: PARTICIPATION_COST,
-        is_active,
+        status,
+        reserved_gas: SETTLEMENT_GAS,
+        player_stats: player_stats.clone().into_iter().collect(),
+        leaderboards: leaderboards.clone(),
+        token_reply_timeout: Some(DEFAULT_TOKEN_REPLY_TIMEOUT),
         ..Default::default()
     });
 
@@ -111,18 +150,26 @@ fn two_rounds_and_meta_state() {
         let prize_fund = PARTICIPATION_COST * (index + 1) as u128;
 
         players.push(player.into());
+        update_stats(&mut player_stats, player, 2, None, None);
 
         goc.enter_with_value(player, PARTICIPATION_COST)
-            .succeed(player);
+            .succeed((2, player));
         assert_eq!(system.balance_of(goc.actor_id().as_ref()), prize_fund);
         goc.state().all().eq(State {
             admin,
+            existential_deposit: EXISTENTIAL_DEPOSIT,
             started,
             ending,
+            round: 2,
             players: players.clone(),
+            stakes: vec![PARTICIPATION_COST; index + 1],
             prize_fund,
             participation_cost: PARTICIPATION_COST,
-            is_active,
+            status,
+            reserved_gas: SETTLEMENT_GAS,
+            player_stats: player_stats.clone().into_iter().collect(),
+            leaderboards: leaderboards.clone(),
+            token_reply_timeout: Some(DEFAULT_TOKEN_REPLY_TIMEOUT),
             ..Default::default()
         });
     }
@@ -131,21 +178,61 @@ fn two_rounds_and_meta_state() {
 
     let winner: [u8; 32] = utils::predict_winner(&system, &PLAYERS).into();
 
-    goc.pick_winner(ADMIN).succeed(winner.into());
+    goc.pick_winner(ADMIN)
+        .succeed((2, winner.into(), PARTICIPATION_COST * 3, 0));
+    update_stats(
+        &mut player_stats,
+        winner,
+        2,
+        None,
+        Some(PARTICIPATION_COST * 3),
+    );
+    leaderboards.insert(0, (None, vec![(winner.into(), PARTICIPATION_COST * 3)]));
     system.claim_value_from_mailbox(winner);
     assert_eq!(system.balance_of(winner), PARTICIPATION_COST * 2 + AMOUNT);
     goc.state().all().eq(State {
         admin,
+        existential_deposit: EXISTENTIAL_DEPOSIT,
         started,
         ending,
+        round: 2,
         players,
+        stakes: vec![PARTICIPATION_COST; 3],
         prize_fund: PARTICIPATION_COST * 3,
         participation_cost: PARTICIPATION_COST,
-        winner: winner.into(),
+        winner: Some(winner.into()),
+        winners: vec![winner.into()],
+        status: RoundStatus::Finished,
+        player_stats: player_stats.into_iter().collect(),
+        leaderboards,
+        token_reply_timeout: Some(DEFAULT_TOKEN_REPLY_TIMEOUT),
         ..Default::default()
     });
 }
 
+/// Updates expected [`PlayerStats`] of `player` after an entry, or after a win
+/// if there's `prize`.
+fn update_stats(
+    player_stats: &mut BTreeMap<ActorId, PlayerStats>,
+    player: impl Into<ActorId>,
+    round: u64,
+    currency: Option<ActorId>,
+    prize: Option<u128>,
+) {
+    let stats: &mut PlayerStats = player_stats.entry(player.into()).or_default();
+    let currency_stats = stats.currencies.entry(currency).or_default();
+
+    if let Some(prize) = prize {
+        stats.wins += 1;
+        currency_stats.won += prize;
+    } else {
+        stats.rounds_entered += 1;
+        currency_stats.spent += PARTICIPATION_COST;
+    }
+
+    stats.last_round = round;
+}
+
 #[test]
 fn failures() {
     let system = utils::initialize_system();
@@ -155,42 +242,58 @@ fn failures() {
     let mut goc = Goc::initialize(&system, ADMIN).succeed();
     goc.start(FOREIGN_USER, 0, 0, None)
         .failed(Error::AccessRestricted);
+    goc.configure_token_calls(FOREIGN_USER, 0, None)
+        .failed(Error::AccessRestricted);
 
     goc.start(ADMIN, 0, 0, Some(ActorId::zero()))
         .failed(Error::ZeroActorId);
 
-    goc.enter(PLAYERS[0]).failed(Error::UnexpectedGameStatus);
+    goc.enter(PLAYERS[0]).failed(Error::NoRoundStarted);
+    goc.pick_winner(ADMIN).failed(Error::NoRoundStarted);
 
     goc.start(ADMIN, DURATION, PARTICIPATION_COST, None)
         .succeed((
+            1,
             system.block_timestamp() + DURATION,
             PARTICIPATION_COST,
             None,
         ));
-    goc.start(ADMIN, 0, 0, None)
-        .failed(Error::UnexpectedGameStatus);
+    goc.start(ADMIN, 0, 0, None).failed(Error::RoundIsActive {
+        status: RoundStatus::Entry,
+        ending: system.block_timestamp() + DURATION,
+    });
 
     system.mint_to(PLAYERS[0], AMOUNT);
     goc.enter_with_value(PLAYERS[0], PARTICIPATION_COST)
-        .succeed(PLAYERS[0]);
+        .succeed((1, PLAYERS[0]));
     goc.enter(PLAYERS[0]).failed(Error::AlreadyParticipating);
 
     system.mint_to(PLAYERS[1], AMOUNT);
-    goc.enter_with_value(PLAYERS[1], PARTICIPATION_COST + 1)
-        .failed(Error::InvalidParticipationCost);
-
-    system.claim_value_from_mailbox(PLAYERS[1]);
     goc.enter_with_value(PLAYERS[1], PARTICIPATION_COST - 1)
-        .failed(Error::InvalidParticipationCost);
+        .failed(Error::InvalidParticipationCost {
+            expected: PARTICIPATION_COST,
+            sent: PARTICIPATION_COST - 1,
+        });
 
     goc.pick_winner(FOREIGN_USER)
         .failed(Error::AccessRestricted);
 
-    goc.pick_winner(ADMIN).failed(Error::UnexpectedGameStatus);
+    goc.pick_winner(ADMIN).failed(Error::EntryStageNotOver {
+        ending: system.block_timestamp() + DURATION,
+        now: system.block_timestamp(),
+    });
 
     system.spend_blocks(DURATION_IN_SECS);
-    goc.pick_winner(ADMIN).succeed(PLAYERS[0].into());
-    goc.pick_winner(ADMIN).failed(Error::UnexpectedGameStatus);
+    assert_eq!(goc.state().all().0.status, RoundStatus::AwaitingDraw);
+    goc.pick_winner(ADMIN)
+        .succeed((1, PLAYERS[0].into(), PARTICIPATION_COST, 0));
+    goc.pick_winner(ADMIN).failed(Error::WinnerAlreadyPicked {
+        winner: PLAYERS[0].into(),
+    });
+    goc.enter(PLAYERS[2]).failed(Error::EntryStageOver {
+        ending: system.block_timestamp(),
+        now: system.block_timestamp(),
+    });
 }
 
 #[test]
@@ -199,8 +302,415 @@ fn round_without_players() {
     let mut goc = Goc::initialize(&system, ADMIN).succeed();
 
     goc.start(ADMIN, 0, 0, None)
-        .succeed((system.block_timestamp(), 0, None));
-    goc.pick_winner(ADMIN).succeed(ActorId::zero());
+        .succeed((1, system.block_timestamp(), 0, None));
+    goc.pick_winner_in_empty_round(ADMIN, None).succeed(1);
+    goc.pick_winner(ADMIN).failed(Error::RoundIsCancelled);
+    goc.state().all().eq(State {
+        admin: ADMIN.into(),
+        existential_deposit: EXISTENTIAL_DEPOSIT,
+        started: system.block_timestamp(),
+        ending: system.block_timestamp(),
+        round: 1,
+        status: RoundStatus::Cancelled,
+        reserved_gas: SETTLEMENT_GAS,
+        token_reply_timeout: Some(DEFAULT_TOKEN_REPLY_TIMEOUT),
+        ..Default::default()
+    });
+}
+
+#[test]
+fn gas_reservation() {
+    const GAS: u64 = 1_000_000_000;
+    const RESERVATION_DURATION: u32 = 100;
+
+    let system = utils::initialize_system();
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    goc.gas_reservation(ADMIN).succeed((0, None));
+    goc.start(ADMIN, DURATION, PARTICIPATION_COST, None)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            None,
+        ));
+    assert_eq!(goc.state().all().0.reserved_gas, SETTLEMENT_GAS);
+
+    goc.reserve_gas(FOREIGN_USER, GAS, RESERVATION_DURATION)
+        .failed(Error::AccessRestricted);
+    goc.gas_reservation(FOREIGN_USER)
+        .failed(Error::AccessRestricted);
+
+    let expiration = system.block_height() + RESERVATION_DURATION;
+
+    goc.reserve_gas(ADMIN, GAS, RESERVATION_DURATION)
+        .succeed((GAS, expiration));
+    goc.gas_reservation(ADMIN)
+        .succeed((SETTLEMENT_GAS + GAS, Some(expiration)));
+
+    system.mint_to(PLAYERS[0], AMOUNT);
+    goc.enter_with_value(PLAYERS[0], PARTICIPATION_COST)
+        .succeed((1, PLAYERS[0]));
+
+    system.spend_blocks(DURATION_IN_SECS);
+    goc.pick_winner(ADMIN)
+        .succeed((1, PLAYERS[0].into(), PARTICIPATION_COST, 0));
+    assert_eq!(goc.state().all().0.reserved_gas, SETTLEMENT_GAS);
+}
+
+#[test]
+fn dust() {
+    const PARTICIPATION_COST: u128 = EXISTENTIAL_DEPOSIT - 1;
+
+    let system = utils::initialize_system();
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    for player in PLAYERS {
+        system.mint_to(player, AMOUNT);
+    }
+
+    goc.start(ADMIN, DURATION, PARTICIPATION_COST, None)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            None,
+        ));
+    goc.enter_with_value(PLAYERS[0], PARTICIPATION_COST)
+        .succeed((1, PLAYERS[0]));
+
+    system.spend_blocks(DURATION_IN_SECS);
+    goc.pick_winner(ADMIN)
+        .succeed((1, PLAYERS[0].into(), 0, PARTICIPATION_COST));
+    assert_eq!(goc.state().all().0.dust, PARTICIPATION_COST);
+
+    goc.start(ADMIN, DURATION, PARTICIPATION_COST, None)
+        .succeed((
+            2,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            None,
+        ));
+    goc.enter_with_value(PLAYERS[1], 1)
+        .failed(Error::InvalidParticipationCost {
+            expected: PARTICIPATION_COST,
+            sent: 1,
+        });
+    assert_eq!(goc.state().all().0.dust, PARTICIPATION_COST + 1);
+    goc.enter_with_value(PLAYERS[1], PARTICIPATION_COST)
+        .succeed((2, PLAYERS[1]));
+
+    let prize = PARTICIPATION_COST * 2 + 1;
+
+    system.spend_blocks(DURATION_IN_SECS);
+    goc.pick_winner(ADMIN)
+        .succeed((2, PLAYERS[1].into(), prize, 0));
+    system.claim_value_from_mailbox(PLAYERS[1]);
+    assert_eq!(
+        system.balance_of(PLAYERS[1]),
+        AMOUNT - PARTICIPATION_COST - 1 + prize
+    );
+    assert_eq!(goc.state().all().0.dust, 0);
+}
+
+#[test]
+fn notifications() {
+    const SUBSCRIBER: u64 = 7;
+
+    let system = utils::initialize_system();
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    goc.subscribe(FOREIGN_USER, SUBSCRIBER)
+        .failed(Error::AccessRestricted);
+    goc.unsubscribe(ADMIN, SUBSCRIBER)
+        .failed(Error::NotSubscribed);
+    goc.subscribe(ADMIN, 0).failed(Error::ZeroActorId);
+
+    let timestamp = system.block_timestamp();
+
+    goc.subscribe(ADMIN, SUBSCRIBER)
+        .sent(
+            SUBSCRIBER,
+            Notification {
+                round: 0,
+                sequence: 0,
+                timestamp,
+                event: Event::Subscribed(SUBSCRIBER.into()),
+            },
+        )
+        .succeed(SUBSCRIBER);
+    goc.subscribe(ADMIN, SUBSCRIBER)
+        .failed(Error::AlreadySubscribed);
+
+    let event = Event::Started {
+        round: 1,
+        ending: timestamp + DURATION,
+        participation_cost: PARTICIPATION_COST,
+        fungible_token: None,
+        selection: Selection::Uniform,
+        raffle: None,
+        pricing: Pricing::Fixed,
+    };
+    let notification = Notification {
+        round: 1,
+        sequence: 1,
+        timestamp,
+        event,
+    };
+
+    goc.start(ADMIN, DURATION, PARTICIPATION_COST, None)
+        .sent(SUBSCRIBER, &notification)
+        .sent(0, &notification)
+        .succeed((1, timestamp + DURATION, PARTICIPATION_COST, None));
+    goc.state().all().eq(State {
+        admin: ADMIN.into(),
+        existential_deposit: EXISTENTIAL_DEPOSIT,
+        started: timestamp,
+        ending: timestamp + DURATION,
+        round: 1,
+        participation_cost: PARTICIPATION_COST,
+        status: RoundStatus::Entry,
+        reserved_gas: SETTLEMENT_GAS,
+        subscribers: vec![SUBSCRIBER.into()],
+        token_reply_timeout: Some(DEFAULT_TOKEN_REPLY_TIMEOUT),
+        ..Default::default()
+    });
+
+    goc.unsubscribe(ADMIN, SUBSCRIBER)
+        .sent(
+            0,
+            Notification {
+                round: 1,
+                sequence: 2,
+                timestamp,
+                event: Event::Unsubscribed(SUBSCRIBER.into()),
+            },
+        )
+        .succeed(SUBSCRIBER);
+    assert!(goc.state().all().0.subscribers.is_empty());
+}
+
+#[test]
+fn round_mismatch() {
+    let system = utils::initialize_system();
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    goc.enter_in_round(PLAYERS[0], Some(1), 0)
+        .failed(Error::UnexpectedRound {
+            expected: 1,
+            current: 0,
+        });
+    goc.start(ADMIN, 0, 0, None)
+        .succeed((1, system.block_timestamp(), 0, None));
+    goc.pick_winner_in_round(ADMIN, Some(0))
+        .failed(Error::UnexpectedRound {
+            expected: 0,
+            current: 1,
+        });
+    goc.pick_winner_in_empty_round(ADMIN, Some(1)).succeed(1);
+
+    goc.start(ADMIN, DURATION, 0, None)
+        .succeed((2, system.block_timestamp() + DURATION, 0, None));
+    goc.enter_in_round(PLAYERS[0], Some(1), 0)
+        .failed(Error::UnexpectedRound {
+            expected: 1,
+            current: 2,
+        });
+    goc.enter_in_round(PLAYERS[0], Some(2), 0)
+        .succeed((2, PLAYERS[0]));
+    assert_eq!(goc.state().all().0.round, 2);
+}
+
+#[test]
+fn player_stats() {
+    let system = utils::initialize_system();
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    for player in PLAYERS {
+        system.mint_to(player, PARTICIPATION_COST * 2);
+    }
+
+    goc.start(ADMIN, DURATION, PARTICIPATION_COST, None)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            None,
+        ));
+
+    for player in &PLAYERS[..2] {
+        goc.enter_with_value(*player, PARTICIPATION_COST)
+            .succeed((1, *player));
+    }
+
+    system.spend_blocks(DURATION_IN_SECS);
+
+    let first_winner = utils::predict_winner(&system, &PLAYERS[..2]);
+
+    goc.pick_winner(ADMIN)
+        .succeed((1, first_winner, PARTICIPATION_COST * 2, 0));
+    goc.start(ADMIN, DURATION, PARTICIPATION_COST, None)
+        .succeed((
+            2,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            None,
+        ));
+    goc.enter_with_value(PLAYERS[1], PARTICIPATION_COST)
+        .succeed((2, PLAYERS[1]));
+    system.spend_blocks(DURATION_IN_SECS);
+    goc.pick_winner(ADMIN)
+        .succeed((2, PLAYERS[1].into(), PARTICIPATION_COST, 0));
+
+    let stats = |player: u64, rounds_entered: u64, mut wins: u64, last_round| {
+        let mut won = PARTICIPATION_COST * (wins as u128);
+
+        if first_winner == ActorId::from(player) {
+            wins += 1;
+            won += PARTICIPATION_COST * 2;
+        }
+
+        let currency_stats = CurrencyStats {
+            spent: PARTICIPATION_COST * rounds_entered as u128,
+            won,
+        };
+
+        (
+            ActorId::from(player),
+            PlayerStats {
+                rounds_entered,
+                wins,
+                last_round,
+                currencies: [(None, currency_stats)].into(),
+            },
+        )
+    };
+    let first_stats = stats(PLAYERS[0], 1, 0, 1);
+    let second_stats = stats(PLAYERS[1], 2, 1, 2);
+
+    let state = goc.state().all().0;
+
+    assert_eq!(
+        state.player_stats,
+        [first_stats.clone(), second_stats.clone()]
+    );
+
+    let mut leaderboard: Vec<_> = [first_stats, second_stats]
+        .into_iter()
+        .map(|(player, stats)| (player, stats.currencies[&None].won))
+        .filter(|(_, won)| *won != 0)
+        .collect();
+
+    leaderboard.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    assert_eq!(state.leaderboards, [(None, leaderboard)]);
+}
+
+#[test]
+fn value_recovery() {
+    const STRAY_VALUE: u128 = 5000;
+
+    let system = utils::initialize_system();
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    goc.start(ADMIN, DURATION, PARTICIPATION_COST, None)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            None,
+        ));
+
+    system.mint_to(PLAYERS[0], AMOUNT);
+    goc.enter_with_value(PLAYERS[0], PARTICIPATION_COST)
+        .succeed((1, PLAYERS[0]));
+    system.mint_to(goc.actor_id().as_ref(), STRAY_VALUE);
+
+    goc.recover_value(FOREIGN_USER, PLAYERS[1], STRAY_VALUE)
+        .failed(Error::AccessRestricted);
+    goc.recover_value(ADMIN, ActorId::zero(), STRAY_VALUE)
+        .failed(Error::ZeroActorId);
+    goc.recover_value(ADMIN, PLAYERS[1], STRAY_VALUE + 1)
+        .failed(Error::InsufficientSurplus {
+            surplus: STRAY_VALUE,
+        });
+    goc.recover_value(ADMIN, PLAYERS[1], EXISTENTIAL_DEPOSIT - 1)
+        .failed(Error::BelowExistentialDeposit {
+            existential_deposit: EXISTENTIAL_DEPOSIT,
+        });
+
+    goc.recover_value(ADMIN, PLAYERS[1], STRAY_VALUE)
+        .succeed((PLAYERS[1].into(), STRAY_VALUE));
+    system.claim_value_from_mailbox(PLAYERS[1]);
+    assert_eq!(system.balance_of(PLAYERS[1]), STRAY_VALUE);
+    assert_eq!(
+        system.balance_of(goc.actor_id().as_ref()),
+        PARTICIPATION_COST
+    );
+
+    goc.recover_value(ADMIN, PLAYERS[1], 1)
+        .failed(Error::InsufficientSurplus { surplus: 0 });
+}
+
+#[test]
+fn token_calls_configuration() {
+    const REPLY_DEPOSIT: u64 = 1_000_000_000;
+    const REPLY_TIMEOUT: Option<u32> = Some(DURATION_IN_SECS);
+
+    let system = utils::initialize_system();
+
+    let mut fungible_token = FungibleToken::initialize(&system);
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    let ft_actor_id = Some(fungible_token.actor_id());
+
+    goc.configure_token_calls(ADMIN, REPLY_DEPOSIT, REPLY_TIMEOUT)
+        .succeed((REPLY_DEPOSIT, REPLY_TIMEOUT));
+    goc.start(ADMIN, DURATION, PARTICIPATION_COST, ft_actor_id)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            ft_actor_id,
+        ));
+
+    fungible_token.mint(PLAYERS[0], AMOUNT);
+    fungible_token.approve(PLAYERS[0], goc.actor_id(), PARTICIPATION_COST);
+    goc.enter(PLAYERS[0]).succeed((1, PLAYERS[0]));
+
+    goc.state().all().eq(State {
+        admin: ADMIN.into(),
+        existential_deposit: EXISTENTIAL_DEPOSIT,
+        started: system.block_timestamp(),
+        ending: system.block_timestamp() + DURATION,
+        round: 1,
+        players: vec![PLAYERS[0].into()],
+        stakes: vec![PARTICIPATION_COST],
+        prize_fund: PARTICIPATION_COST,
+        participation_cost: PARTICIPATION_COST,
+        fungible_token: ft_actor_id,
+        status: RoundStatus::Entry,
+        reserved_gas: SETTLEMENT_GAS,
+        token_reply_deposit: REPLY_DEPOSIT,
+        token_reply_timeout: REPLY_TIMEOUT,
+        player_stats: vec![(
+            PLAYERS[0].into(),
+            PlayerStats {
+                rounds_entered: 1,
+                wins: 0,
+                last_round: 1,
+                currencies: [(
+                    ft_actor_id,
+                    CurrencyStats {
+                        spent: PARTICIPATION_COST,
+                        won: 0,
+                    },
+                )]
+                .into(),
+            },
+        )],
+        ..Default::default()
+    });
 }
 
 #[test]
@@ -218,24 +728,748 @@ fn overflow() {
     let ft_actor_id = Some(fungible_token.actor_id());
 
     goc.start(ADMIN, DURATION, PARTICIPATION_COST, ft_actor_id)
-        .succeed((ending, PARTICIPATION_COST, ft_actor_id));
+        .succeed((1, ending, PARTICIPATION_COST, ft_actor_id));
 
     for player in PLAYERS.into_iter().take(2) {
         fungible_token.mint(player, AMOUNT);
         fungible_token.approve(player, goc.actor_id(), PARTICIPATION_COST);
 
-        goc.enter(player).succeed(player);
+        goc.enter(player).succeed((1, player));
     }
 
     goc.state().all().eq(State {
         admin: ADMIN.into(),
+        existential_deposit: EXISTENTIAL_DEPOSIT,
         started: system.block_timestamp(),
         ending,
+        round: 1,
         players: vec![PLAYERS[0].into(), PLAYERS[1].into()],
+        stakes: vec![PARTICIPATION_COST; 2],
         prize_fund: u128::MAX,
         participation_cost: PARTICIPATION_COST,
         fungible_token: ft_actor_id,
-        is_active: true,
+        status: RoundStatus::Entry,
+        reserved_gas: SETTLEMENT_GAS,
+        player_stats: PLAYERS[..2]
+            .iter()
+            .map(|player| {
+                let stats = PlayerStats {
+                    rounds_entered: 1,
+                    wins: 0,
+                    last_round: 1,
+                    currencies: [(
+                        ft_actor_id,
+                        CurrencyStats {
+                            spent: u128::MAX,
+                            won: 0,
+                        },
+                    )]
+                    .into(),
+                };
+
+                ((*player).into(), stats)
+            })
+            .collect(),
+        token_reply_timeout: Some(DEFAULT_TOKEN_REPLY_TIMEOUT),
         ..Default::default()
     })
 }
+
+#[test]
+fn failed_token_transfer() {
+    let system = utils::initialize_system();
+
+    let mock = MockFungibleToken::initialize(&system, Behaviour::FailNthTransfer(2));
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    let ft_actor_id = Some(mock.actor_id());
+
+    goc.start(ADMIN, DURATION, PARTICIPATION_COST, ft_actor_id)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            ft_actor_id,
+        ));
+    goc.enter(PLAYERS[0]).succeed((1, PLAYERS[0]));
+    goc.enter(PLAYERS[1]).failed(Error::TokenTransferFailed);
+    goc.enter(PLAYERS[1]).succeed((1, PLAYERS[1]));
+
+    // A replied transaction is removed from the cache, so a next attempt gets
+    // a new transaction ID.
+    assert_eq!(
+        mock.state().transfers,
+        [(0, PLAYERS[0]), (1, PLAYERS[1]), (2, PLAYERS[1])].map(|(transaction_id, player)| {
+            Transfer {
+                source: goc.actor_id(),
+                transaction_id,
+                sender: player.into(),
+                recipient: goc.actor_id(),
+                amount: PARTICIPATION_COST,
+            }
+        })
+    );
+    assert_eq!(
+        goc.state().all().0.players,
+        [ActorId::from(PLAYERS[0]), PLAYERS[1].into()]
+    );
+}
+
+#[test]
+fn token_timeout() {
+    const REPLY_TIMEOUT: u32 = 2;
+
+    let system = utils::initialize_system();
+
+    let mock = MockFungibleToken::initialize(&system, Behaviour::NeverReplyToNthTransfer(1));
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    let ft_actor_id = Some(mock.actor_id());
+
+    goc.configure_token_calls(ADMIN, 0, Some(REPLY_TIMEOUT))
+        .succeed((0, Some(REPLY_TIMEOUT)));
+    goc.start(ADMIN, DURATION * 10, PARTICIPATION_COST, ft_actor_id)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION * 10,
+            PARTICIPATION_COST,
+            ft_actor_id,
+        ));
+
+    assert!(!goc.enter(PLAYERS[0]).result.main_failed());
+
+    system.spend_blocks(REPLY_TIMEOUT);
+    assert!(goc.state().all().0.players.is_empty());
+
+    goc.enter(PLAYERS[0]).succeed((1, PLAYERS[0]));
+
+    // A transaction without a reply stays cached, so the same transaction ID
+    // is used on a next attempt.
+    assert_eq!(
+        mock.state()
+            .transfers
+            .into_iter()
+            .map(|transfer| (transfer.sender, transfer.transaction_id))
+            .collect::<Vec<_>>(),
+        [(ActorId::from(PLAYERS[0]), 0); 2]
+    );
+}
+
+#[test]
+fn unexpected_token_reply() {
+    let system = utils::initialize_system();
+
+    let mock = MockFungibleToken::initialize(&system, Behaviour::UnexpectedVariantOnNthTransfer(1));
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    let ft_actor_id = Some(mock.actor_id());
+
+    goc.start(ADMIN, DURATION, PARTICIPATION_COST, ft_actor_id)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            ft_actor_id,
+        ));
+
+    goc.enter(PLAYERS[0]).failed(Error::UnexpectedTokenReply);
+
+    let state = goc.state().all().0;
+
+    assert!(state.players.is_empty());
+    assert_eq!(state.prize_fund, 0);
+
+    goc.enter(PLAYERS[0]).succeed((1, PLAYERS[0]));
+
+    // Unlike a timed out one, the transaction isn't kept, so a next attempt
+    // makes a new one.
+    assert_eq!(
+        mock.state()
+            .transfers
+            .into_iter()
+            .map(|transfer| (transfer.sender, transfer.transaction_id))
+            .collect::<Vec<_>>(),
+        [
+            (ActorId::from(PLAYERS[0]), 0),
+            (ActorId::from(PLAYERS[0]), 1)
+        ]
+    );
+    assert_eq!(goc.state().all().0.players, [PLAYERS[0].into()]);
+}
+
+#[test]
+fn racing_token_entries() {
+    const DELAY: u32 = DURATION_IN_SECS + 1;
+
+    let system = utils::initialize_system();
+
+    let mock = MockFungibleToken::initialize(&system, Behaviour::DelayReplies(DELAY));
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    let ft_actor_id = Some(mock.actor_id());
+    let raffle = Raffle {
+        tickets: 1,
+        unsold: UnsoldTickets::Draw,
+    };
+
+    let options = StartOptions {
+        raffle: Some(raffle),
+        ..Default::default()
+    };
+
+    goc.start_with(ADMIN, DURATION, PARTICIPATION_COST, ft_actor_id, options)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            ft_actor_id,
+            options,
+        ));
+
+    // The first entry holds the last ticket while its transfer is awaited.
+    assert!(!goc.enter(PLAYERS[0]).result.main_failed());
+    goc.enter(PLAYERS[0]).failed(Error::AlreadyParticipating);
+    goc.enter(PLAYERS[1]).failed(Error::SoldOut);
+
+    system.spend_blocks(DURATION_IN_SECS);
+    goc.pick_winner(ADMIN).failed(Error::EntriesPending);
+
+    system.spend_blocks(DELAY - DURATION_IN_SECS);
+
+    let state = goc.state().all().0;
+
+    assert_eq!(state.players, [PLAYERS[0].into()]);
+    assert_eq!(state.prize_fund, PARTICIPATION_COST);
+    assert_eq!(
+        mock.state().transfers,
+        [Transfer {
+            source: goc.actor_id(),
+            transaction_id: 0,
+            sender: PLAYERS[0].into(),
+            recipient: goc.actor_id(),
+            amount: PARTICIPATION_COST,
+        }]
+    );
+}
+
+#[test]
+fn multiple_winners() {
+    let system = utils::initialize_system();
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    goc.start_with(
+        ADMIN,
+        DURATION,
+        0,
+        None,
+        StartOptions {
+            selection: Selection::Multiple(0),
+            ..Default::default()
+        },
+    )
+    .failed(Error::InvalidSelection);
+
+    let selection = Selection::Multiple(2);
+
+    let options = StartOptions {
+        selection,
+        ..Default::default()
+    };
+
+    goc.start_with(ADMIN, DURATION, PARTICIPATION_COST, None, options)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            None,
+            options,
+        ));
+
+    for player in PLAYERS {
+        system.mint_to(player, PARTICIPATION_COST);
+        goc.enter_with_value(player, PARTICIPATION_COST)
+            .succeed((1, player));
+    }
+
+    system.spend_blocks(DURATION_IN_SECS);
+
+    let stakes = [PARTICIPATION_COST; 3];
+    let winners = utils::predict_winners(&system, selection, &PLAYERS, &stakes);
+    let prize = PARTICIPATION_COST * 3 / 2;
+
+    assert_eq!(winners.len(), 2);
+    assert_ne!(winners[0], winners[1]);
+
+    goc.pick_winners(ADMIN).succeed((
+        1,
+        winners.iter().map(|winner| (*winner, prize)).collect(),
+        0,
+    ));
+
+    for winner in &winners {
+        let winner: [u8; 32] = (*winner).into();
+
+        system.claim_value_from_mailbox(winner);
+        assert_eq!(system.balance_of(winner), prize);
+    }
+
+    let state = goc.state().all().0;
+
+    assert_eq!(state.selection, selection);
+    assert_eq!(state.stakes, stakes);
+    assert_eq!(state.winners, winners);
+    assert_eq!(state.winner, Some(winners[0]));
+    assert_eq!(state.status, RoundStatus::Finished);
+}
+
+#[test]
+fn weighted_selection() {
+    let system = utils::initialize_system();
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    let options = StartOptions {
+        selection: Selection::Weighted,
+        ..Default::default()
+    };
+
+    goc.start_with(ADMIN, DURATION, PARTICIPATION_COST, None, options)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            None,
+            options,
+        ));
+
+    for player in PLAYERS {
+        system.mint_to(player, PARTICIPATION_COST);
+        goc.enter_with_value(player, PARTICIPATION_COST)
+            .succeed((1, player));
+    }
+
+    system.spend_blocks(DURATION_IN_SECS);
+
+    let winners = utils::predict_winners(
+        &system,
+        Selection::Weighted,
+        &PLAYERS,
+        &[PARTICIPATION_COST; 3],
+    );
+
+    goc.pick_winner(ADMIN)
+        .succeed((1, winners[0], PARTICIPATION_COST * 3, 0));
+}
+
+#[test]
+fn multiple_winners_partial_settlement() {
+    let system = utils::initialize_system();
+
+    // The first 3 transfers are entries, so the second prize transfer fails.
+    let mock = MockFungibleToken::initialize(&system, Behaviour::FailNthTransfer(5));
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    let ft_actor_id = Some(mock.actor_id());
+    let selection = Selection::Multiple(2);
+
+    let options = StartOptions {
+        selection,
+        ..Default::default()
+    };
+
+    goc.start_with(ADMIN, DURATION, PARTICIPATION_COST, ft_actor_id, options)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            ft_actor_id,
+            options,
+        ));
+
+    for player in PLAYERS {
+        goc.enter(player).succeed((1, player));
+    }
+
+    system.spend_blocks(DURATION_IN_SECS);
+
+    let winners = utils::predict_winners(&system, selection, &PLAYERS, &[PARTICIPATION_COST; 3]);
+    let prize = PARTICIPATION_COST * 3 / 2;
+
+    goc.pick_winners(ADMIN).failed(Error::TokenTransferFailed);
+    assert_eq!(goc.state().all().0.status, RoundStatus::Settling);
+
+    // Winners are cached, so they're the same after some blocks.
+    system.spend_blocks(1);
+    goc.pick_winners(ADMIN).succeed((
+        1,
+        winners.iter().map(|winner| (*winner, prize)).collect(),
+        0,
+    ));
+
+    // The first winner is paid only once.
+    assert_eq!(
+        mock.state().transfers[PLAYERS.len()..]
+            .iter()
+            .map(|transfer| transfer.recipient)
+            .collect::<Vec<_>>(),
+        [winners[0], winners[1], winners[1]]
+    );
+}
+
+#[test]
+fn raffle_sold_out() {
+    let system = utils::initialize_system();
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    let raffle = Raffle {
+        tickets: 2,
+        unsold: UnsoldTickets::Refund,
+    };
+    let ending = system.block_timestamp() + DURATION;
+
+    let options = StartOptions {
+        raffle: Some(raffle),
+        ..Default::default()
+    };
+
+    goc.start_with(ADMIN, DURATION, PARTICIPATION_COST, None, options)
+        .succeed((1, ending, PARTICIPATION_COST, None, options));
+
+    for player in PLAYERS {
+        system.mint_to(player, PARTICIPATION_COST);
+    }
+
+    goc.enter_with_value(PLAYERS[0], PARTICIPATION_COST)
+        .succeed((1, PLAYERS[0]));
+    goc.pick_winner(ADMIN).failed(Error::EntryStageNotOver {
+        ending,
+        now: system.block_timestamp(),
+    });
+    goc.enter_with_value(PLAYERS[1], PARTICIPATION_COST)
+        .succeed((1, PLAYERS[1]));
+    goc.enter_with_value(PLAYERS[2], PARTICIPATION_COST)
+        .failed(Error::SoldOut);
+
+    // The last sold ticket ends the players entry stage.
+    assert_eq!(goc.state().all().0.status, RoundStatus::AwaitingDraw);
+
+    let winner = utils::predict_winner(&system, &PLAYERS[..2]);
+
+    goc.pick_winner(ADMIN)
+        .succeed((1, winner, PARTICIPATION_COST * 2, 0));
+    goc.enter_with_value(PLAYERS[2], PARTICIPATION_COST)
+        .failed(Error::SoldOut);
+
+    goc.start_with(
+        ADMIN,
+        DURATION,
+        PARTICIPATION_COST,
+        None,
+        StartOptions {
+            raffle: Some(Raffle {
+                tickets: 0,
+                unsold: UnsoldTickets::Draw,
+            }),
+            ..Default::default()
+        },
+    )
+    .failed(Error::InvalidRaffle);
+}
+
+#[test]
+fn raffle_refund() {
+    let system = utils::initialize_system();
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    let raffle = Raffle {
+        tickets: 3,
+        unsold: UnsoldTickets::Refund,
+    };
+
+    let options = StartOptions {
+        raffle: Some(raffle),
+        ..Default::default()
+    };
+
+    goc.start_with(ADMIN, DURATION, PARTICIPATION_COST, None, options)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            None,
+            options,
+        ));
+
+    for player in &PLAYERS[..2] {
+        system.mint_to(*player, PARTICIPATION_COST);
+        goc.enter_with_value(*player, PARTICIPATION_COST)
+            .succeed((1, *player));
+    }
+
+    system.spend_blocks(DURATION_IN_SECS);
+
+    goc.pick_winner_with_refund(ADMIN)
+        .succeed((1, PARTICIPATION_COST * 2, 0));
+
+    for player in &PLAYERS[..2] {
+        system.claim_value_from_mailbox(*player);
+        assert_eq!(system.balance_of(*player), PARTICIPATION_COST);
+    }
+
+    let state = goc.state().all().0;
+
+    assert_eq!(state.status, RoundStatus::Cancelled);
+    assert_eq!(state.winner, None);
+    goc.pick_winner(ADMIN).failed(Error::RoundIsCancelled);
+
+    // Unsold tickets don't matter with `UnsoldTickets::Draw`.
+    let raffle = Raffle {
+        tickets: 3,
+        unsold: UnsoldTickets::Draw,
+    };
+
+    let options = StartOptions {
+        raffle: Some(raffle),
+        ..Default::default()
+    };
+
+    goc.start_with(ADMIN, DURATION, PARTICIPATION_COST, None, options)
+        .succeed((
+            2,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            None,
+            options,
+        ));
+    goc.enter_with_value(PLAYERS[0], PARTICIPATION_COST)
+        .succeed((2, PLAYERS[0]));
+    system.spend_blocks(DURATION_IN_SECS);
+    goc.pick_winner(ADMIN)
+        .succeed((2, PLAYERS[0].into(), PARTICIPATION_COST, 0));
+}
+
+#[test]
+fn raffle_partial_refund() {
+    let system = utils::initialize_system();
+
+    // The first 2 transfers are entries, so the second refund fails.
+    let mock = MockFungibleToken::initialize(&system, Behaviour::FailNthTransfer(4));
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    let ft_actor_id = Some(mock.actor_id());
+    let raffle = Raffle {
+        tickets: 3,
+        unsold: UnsoldTickets::Refund,
+    };
+
+    let options = StartOptions {
+        raffle: Some(raffle),
+        ..Default::default()
+    };
+
+    goc.start_with(ADMIN, DURATION, PARTICIPATION_COST, ft_actor_id, options)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            ft_actor_id,
+            options,
+        ));
+
+    for player in &PLAYERS[..2] {
+        goc.enter(*player).succeed((1, *player));
+    }
+
+    system.spend_blocks(DURATION_IN_SECS);
+
+    goc.pick_winner_with_refund(ADMIN)
+        .failed(Error::TokenTransferFailed);
+    assert_eq!(goc.state().all().0.status, RoundStatus::Settling);
+
+    goc.pick_winner_with_refund(ADMIN)
+        .succeed((1, PARTICIPATION_COST * 2, 0));
+
+    // The first player is refunded only once.
+    assert_eq!(
+        mock.state().transfers[2..]
+            .iter()
+            .map(|transfer| (transfer.recipient, transfer.amount))
+            .collect::<Vec<_>>(),
+        [
+            (PLAYERS[0].into(), PARTICIPATION_COST),
+            (PLAYERS[1].into(), PARTICIPATION_COST),
+            (PLAYERS[1].into(), PARTICIPATION_COST),
+        ]
+    );
+}
+
+#[test]
+fn bonding_curve_pricing() {
+    const STEP: u128 = 1000;
+
+    let system = utils::initialize_system();
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    let pricing = Pricing::Linear { step: STEP };
+
+    let options = StartOptions {
+        pricing,
+        ..Default::default()
+    };
+
+    goc.start_with(ADMIN, DURATION, PARTICIPATION_COST, None, options)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION,
+            PARTICIPATION_COST,
+            None,
+            options,
+        ));
+
+    let prices = [0, 1, 2].map(|entries| PARTICIPATION_COST + STEP * entries);
+
+    for (player, price) in PLAYERS.into_iter().zip(prices) {
+        system.mint_to(player, price);
+        assert_eq!(goc.state().all().0.price, price);
+
+        // The previous price isn't enough after the previous entry.
+        if price != PARTICIPATION_COST {
+            goc.enter_with_value(player, price - STEP)
+                .failed(Error::InvalidParticipationCost {
+                    expected: price,
+                    sent: price - STEP,
+                });
+            system.claim_value_from_mailbox(player);
+        }
+
+        goc.enter_with_value(player, price).succeed((1, player));
+    }
+
+    let state = goc.state().all().0;
+
+    assert_eq!(state.pricing, pricing);
+    assert_eq!(state.price, PARTICIPATION_COST + STEP * 3);
+    assert_eq!(state.stakes, prices);
+    assert_eq!(state.prize_fund, prices.iter().sum());
+
+    system.spend_blocks(DURATION_IN_SECS);
+
+    let winner = utils::predict_winner(&system, &PLAYERS);
+
+    goc.pick_winner(ADMIN)
+        .succeed((1, winner, prices.iter().sum(), 0));
+}
+
+#[test]
+fn dutch_auction_pricing() {
+    const FLOOR: u128 = 2000;
+
+    let system = utils::initialize_system();
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    goc.start_with(
+        ADMIN,
+        DURATION * 4,
+        PARTICIPATION_COST,
+        None,
+        StartOptions {
+            pricing: Pricing::DutchAuction {
+                floor: PARTICIPATION_COST + 1,
+            },
+            ..Default::default()
+        },
+    )
+    .failed(Error::InvalidPricing);
+
+    let pricing = Pricing::DutchAuction { floor: FLOOR };
+
+    let options = StartOptions {
+        pricing,
+        ..Default::default()
+    };
+
+    goc.start_with(ADMIN, DURATION * 4, PARTICIPATION_COST, None, options)
+        .succeed((
+            1,
+            system.block_timestamp() + DURATION * 4,
+            PARTICIPATION_COST,
+            None,
+            options,
+        ));
+
+    // The price decays by 1000 every block of 1000 ms.
+    let decay = (PARTICIPATION_COST - FLOOR) / 8;
+
+    for (blocks, player) in PLAYERS.into_iter().enumerate() {
+        let price = PARTICIPATION_COST - decay * (blocks as u128 * 2 + 1);
+
+        system.spend_blocks(if blocks == 0 { 1 } else { 2 });
+        system.mint_to(player, price);
+        assert_eq!(goc.state().all().0.price, price);
+        goc.enter_with_value(player, price).succeed((1, player));
+    }
+
+    system.spend_blocks(3);
+    assert_eq!(goc.state().all().0.price, FLOOR);
+}
+
+#[test]
+fn token_timeout_pricing() {
+    const REPLY_TIMEOUT: u32 = 2;
+    const STEP: u128 = 1000;
+
+    let system = utils::initialize_system();
+
+    let mock = MockFungibleToken::initialize(&system, Behaviour::NeverReplyToNthTransfer(1));
+    let mut goc = Goc::initialize(&system, ADMIN).succeed();
+
+    let ft_actor_id = Some(mock.actor_id());
+    let pricing = Pricing::Linear { step: STEP };
+
+    goc.configure_token_calls(ADMIN, 0, Some(REPLY_TIMEOUT))
+        .succeed((0, Some(REPLY_TIMEOUT)));
+
+    let options = StartOptions {
+        pricing,
+        ..Default::default()
+    };
+
+    goc.start_with(
+        ADMIN,
+        DURATION * 10,
+        PARTICIPATION_COST,
+        ft_actor_id,
+        options,
+    )
+    .succeed((
+        1,
+        system.block_timestamp() + DURATION * 10,
+        PARTICIPATION_COST,
+        ft_actor_id,
+        options,
+    ));
+
+    assert!(!goc.enter(PLAYERS[0]).result.main_failed());
+    system.spend_blocks(REPLY_TIMEOUT);
+    goc.enter(PLAYERS[1]).succeed((1, PLAYERS[1]));
+
+    // The FT contract executes the retried transaction with the first amount,
+    // so the first price is kept although another player entered since then.
+    goc.enter(PLAYERS[0]).succeed((1, PLAYERS[0]));
+
+    let state = goc.state().all().0;
+
+    assert_eq!(state.stakes, [PARTICIPATION_COST; 2]);
+    assert_eq!(state.price, PARTICIPATION_COST + STEP * 2);
+    assert_eq!(
+        mock.state()
+            .transfers
+            .into_iter()
+            .map(|transfer| (transfer.sender, transfer.amount))
+            .collect::<Vec<_>>(),
+        [
+            (PLAYERS[0].into(), PARTICIPATION_COST),
+            (PLAYERS[1].into(), PARTICIPATION_COST),
+            (PLAYERS[0].into(), PARTICIPATION_COST),
+        ]
+    );
+}
@@ -4,6 +4,16 @@ use gstd::{prelude::*, ActorId};
 use gtest::{Log, Program as InnerProgram, RunResult as InnerRunResult, System};
 use marker::PhantomData;
 
+/// A path to a Wasm binary that `gear-wasm-builder` has built for a dependency.
+///
+/// Follows `CARGO_TARGET_DIR`, so binaries are found in a separate target
+/// directory of test builds.
+pub fn wasm_path(name: &str) -> String {
+    let target_dir = option_env!("CARGO_TARGET_DIR").unwrap_or("target");
+
+    format!("{target_dir}/wasm32-unknown-unknown/debug/{name}.opt.wasm")
+}
+
 pub fn initialize_system() -> System {
     let system = System::new();
 
@@ -65,6 +75,15 @@ impl<T, R: Encode, E: Encode> RunResult<T, R, E> {
         assert_contains(&self.result, payload);
     }
 
+    #[track_caller]
+    pub fn sent(self, destination: u64, payload: impl Encode) -> Self {
+        assert!(self
+            .result
+            .contains(&Log::builder().dest(destination).payload(payload)));
+
+        self
+    }
+
     #[track_caller]
     pub fn failed(self, error: E) {
         self.assert_contains(Err::<R, E>(error));
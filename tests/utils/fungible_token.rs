@@ -1,4 +1,4 @@
-use super::{Program, RunResult, TransactionalProgram, FOREIGN_USER};
+use super::{common::wasm_path, Program, RunResult, TransactionalProgram, FOREIGN_USER};
 use ft_main_io::{FTokenAction, FTokenEvent, InitFToken, LogicAction};
 use gstd::{prelude::*, ActorId};
 use gtest::{Log, Program as InnerProgram, RunResult as InnerRunResult, System};
@@ -20,16 +20,9 @@ impl TransactionalProgram for FungibleToken<'_> {
 impl<'a> FungibleToken<'a> {
     #[track_caller]
     pub fn initialize(system: &'a System) -> Self {
-        let program = InnerProgram::from_file(
-            system,
-            "target/wasm32-unknown-unknown/debug/ft_main.opt.wasm",
-        );
-        let storage_code_id: [u8; 32] = system
-            .submit_code("target/wasm32-unknown-unknown/debug/ft_storage.opt.wasm")
-            .into();
-        let logic_code_id: [u8; 32] = system
-            .submit_code("target/wasm32-unknown-unknown/debug/ft_logic.opt.wasm")
-            .into();
+        let program = InnerProgram::from_file(system, &wasm_path("ft_main"));
+        let storage_code_id: [u8; 32] = system.submit_code(&wasm_path("ft_storage")).into();
+        let logic_code_id: [u8; 32] = system.submit_code(&wasm_path("ft_logic")).into();
 
         assert!(!program
             .send(
@@ -83,6 +76,19 @@ impl<'a> FungibleToken<'a> {
             FTokenEvent::Balance,
         )
     }
+
+    #[track_caller]
+    pub fn balance_of(&self, actor_id: impl Into<ActorId>) -> u128 {
+        self.0
+            .send(FOREIGN_USER, FTokenAction::GetBalance(actor_id.into()))
+            .log()
+            .iter()
+            .find_map(|log| match FTokenEvent::decode(&mut log.payload()) {
+                Ok(FTokenEvent::Balance(balance)) => Some(balance),
+                _ => None,
+            })
+            .expect("the FT contract didn't reply with a balance")
+    }
 }
 
 fn assert_ft_token_event_ok(run_result: InnerRunResult) {
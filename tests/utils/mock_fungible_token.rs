@@ -0,0 +1,26 @@
+use super::{common::wasm_path, Program, FOREIGN_USER};
+use game_of_chance_mock_ft::{Behaviour, MockState};
+use gtest::{Program as InnerProgram, System};
+
+pub struct MockFungibleToken<'a>(InnerProgram<'a>);
+
+impl Program for MockFungibleToken<'_> {
+    fn inner_program(&self) -> &InnerProgram {
+        &self.0
+    }
+}
+
+impl<'a> MockFungibleToken<'a> {
+    #[track_caller]
+    pub fn initialize(system: &'a System, behaviour: Behaviour) -> Self {
+        let program = InnerProgram::from_file(system, &wasm_path("game_of_chance_mock_ft"));
+
+        assert!(!program.send(FOREIGN_USER, behaviour).main_failed());
+
+        Self(program)
+    }
+
+    pub fn state(&self) -> MockState {
+        self.0.read_state().unwrap()
+    }
+}
@@ -1,27 +1,38 @@
+#![allow(dead_code)]
+
 use common::{InitResult, MetaStateReply, Program, RunResult, TransactionalProgram};
+use futures::executor::block_on;
+use game_of_chance_client::{
+    gtest::{GtestError, GtestTransport},
+    Client, ClientResult,
+};
+#[cfg(feature = "testing")]
+use game_of_chance_io::testing::*;
 use game_of_chance_io::*;
 use gstd::{prelude::*, ActorId};
 use gtest::{Program as InnerProgram, System, EXISTENTIAL_DEPOSIT};
-use rand::{RngCore, SeedableRng};
-use rand_xoshiro::Xoshiro128PlusPlus;
 
 mod fungible_token;
+mod mock_fungible_token;
 
 pub mod common;
 pub mod prelude;
 
 pub use common::initialize_system;
 pub use fungible_token::FungibleToken;
+pub use mock_fungible_token::MockFungibleToken;
 
 pub const FOREIGN_USER: u64 = 9999999;
 
 type GOCRunResult<T> = RunResult<T, Event, Error>;
 
-pub struct Goc<'a>(InnerProgram<'a>);
+/// Sends actions through [`Client`], so their encoding isn't duplicated here,
+/// and checks replies in raw logs.
+pub struct Goc<'a>(Client<GtestTransport<'a>>);
 
 impl Program for Goc<'_> {
     fn inner_program(&self) -> &InnerProgram {
-        &self.0
+        self.0.transport().program()
     }
 }
 
@@ -52,15 +63,39 @@ impl<'a> Goc<'a> {
             FOREIGN_USER,
             Initialize {
                 admin: admin.into(),
+                existential_deposit: EXISTENTIAL_DEPOSIT,
             },
         );
         let is_active = system.is_active_program(program.id());
+        let client = Client::new(GtestTransport::new(system, program, FOREIGN_USER));
 
-        InitResult::new(Self(program), result, is_active)
+        InitResult::new(Self(client), result, is_active)
     }
 
     pub fn state(&self) -> GOCMetaState {
-        GOCMetaState(&self.0)
+        GOCMetaState(block_on(self.0.state()).unwrap())
+    }
+
+    fn as_sender(&mut self, from: u64) -> &mut Client<GtestTransport<'a>> {
+        self.0.transport_mut().set_sender(from);
+
+        &mut self.0
+    }
+
+    // The reply (or its absence) is asserted in the raw result, which also
+    // covers a panicked contract.
+    fn result<T>(
+        &mut self,
+        _: ClientResult<Event, GtestError>,
+        event: fn(T) -> Event,
+    ) -> GOCRunResult<T> {
+        RunResult::new(
+            self.0
+                .transport_mut()
+                .take_last_result()
+                .expect("an action should be sent"),
+            event,
+        )
     }
 
     pub fn start(
@@ -69,54 +104,270 @@ impl<'a> Goc<'a> {
         duration: u64,
         participation_cost: u128,
         fungible_token: Option<ActorId>,
-    ) -> GOCRunResult<(u64, u128, Option<ActorId>)> {
-        RunResult::new(
-            self.0.send(
-                from,
-                Action::Start {
-                    duration,
-                    participation_cost,
-                    fungible_token,
-                },
-            ),
-            |(ending, participation_cost, fungible_token)| Event::Started {
-                ending,
-                participation_cost,
-                fungible_token,
+    ) -> GOCRunResult<(u64, u64, u128, Option<ActorId>)> {
+        let reply = self.send_start(
+            from,
+            duration,
+            participation_cost,
+            fungible_token,
+            StartOptions::default(),
+        );
+
+        self.result(
+            reply,
+            |(round, ending, participation_cost, fungible_token)| {
+                StartOptions::default().started(round, ending, participation_cost, fungible_token)
             },
         )
     }
 
-    pub fn enter(&mut self, from: u64) -> GOCRunResult<u64> {
+    pub fn start_with(
+        &mut self,
+        from: u64,
+        duration: u64,
+        participation_cost: u128,
+        fungible_token: Option<ActorId>,
+        options: StartOptions,
+    ) -> GOCRunResult<(u64, u64, u128, Option<ActorId>, StartOptions)> {
+        let reply = self.send_start(from, duration, participation_cost, fungible_token, options);
+
+        self.result(
+            reply,
+            |(round, ending, participation_cost, fungible_token, options)| {
+                options.started(round, ending, participation_cost, fungible_token)
+            },
+        )
+    }
+
+    fn send_start(
+        &mut self,
+        from: u64,
+        duration: u64,
+        participation_cost: u128,
+        fungible_token: Option<ActorId>,
+        options: StartOptions,
+    ) -> ClientResult<Event, GtestError> {
+        block_on(self.as_sender(from).start(
+            duration,
+            participation_cost,
+            fungible_token,
+            options.selection,
+            options.raffle,
+            options.pricing,
+        ))
+    }
+
+    pub fn enter(&mut self, from: u64) -> GOCRunResult<(u64, u64)> {
         self.enter_with_value(from, 0)
     }
 
-    pub fn enter_with_value(&mut self, from: u64, value: u128) -> GOCRunResult<u64> {
+    pub fn enter_with_value(&mut self, from: u64, value: u128) -> GOCRunResult<(u64, u64)> {
+        self.enter_in_round(from, None, value)
+    }
+
+    pub fn enter_in_round(
+        &mut self,
+        from: u64,
+        round: Option<u64>,
+        value: u128,
+    ) -> GOCRunResult<(u64, u64)> {
+        let reply = block_on(self.as_sender(from).enter(round, value));
+
+        self.result(reply, |(round, player)| Event::PlayerAdded {
+            round,
+            player: player.into(),
+        })
+    }
+
+    pub fn pick_winner(&mut self, from: u64) -> GOCRunResult<(u64, ActorId, u128, u128)> {
+        self.pick_winner_in_round(from, None)
+    }
+
+    pub fn pick_winner_in_round(
+        &mut self,
+        from: u64,
+        round: Option<u64>,
+    ) -> GOCRunResult<(u64, ActorId, u128, u128)> {
+        let reply = block_on(self.as_sender(from).pick_winner(round));
+
+        self.result(reply, |(round, winner, prize, dust)| Event::Winner {
+            round,
+            winner,
+            prize,
+            dust,
+        })
+    }
+
+    pub fn pick_winners(&mut self, from: u64) -> GOCRunResult<(u64, Vec<(ActorId, u128)>, u128)> {
+        let reply = block_on(self.as_sender(from).pick_winner(None));
+
+        self.result(reply, |(round, winners, dust)| Event::Winners {
+            round,
+            winners,
+            dust,
+        })
+    }
+
+    pub fn pick_winner_in_empty_round(
+        &mut self,
+        from: u64,
+        round: Option<u64>,
+    ) -> GOCRunResult<u64> {
+        let reply = block_on(self.as_sender(from).pick_winner(round));
+
+        self.result(reply, |round| Event::Cancelled { round })
+    }
+
+    pub fn pick_winner_with_refund(&mut self, from: u64) -> GOCRunResult<(u64, u128, u128)> {
+        let reply = block_on(self.as_sender(from).pick_winner(None));
+
+        self.result(reply, |(round, amount, dust)| Event::Refunded {
+            round,
+            amount,
+            dust,
+        })
+    }
+
+    pub fn recover_value(
+        &mut self,
+        from: u64,
+        to: impl Into<ActorId>,
+        amount: u128,
+    ) -> GOCRunResult<(ActorId, u128)> {
+        let reply = block_on(self.as_sender(from).recover_value(to, amount));
+
+        self.result(reply, |(to, amount)| Event::ValueRecovered { to, amount })
+    }
+
+    pub fn reserve_gas(
+        &mut self,
+        from: u64,
+        amount: u64,
+        duration: u32,
+    ) -> GOCRunResult<(u64, u32)> {
+        let reply = block_on(self.as_sender(from).reserve_gas(amount, duration));
+
+        self.result(reply, |(amount, expiration)| Event::GasReserved {
+            amount,
+            expiration,
+        })
+    }
+
+    pub fn configure_token_calls(
+        &mut self,
+        from: u64,
+        reply_deposit: u64,
+        reply_timeout: Option<u32>,
+    ) -> GOCRunResult<(u64, Option<u32>)> {
+        let reply = block_on(
+            self.as_sender(from)
+                .configure_token_calls(reply_deposit, reply_timeout),
+        );
+
+        self.result(reply, |(reply_deposit, reply_timeout)| {
+            Event::TokenCallsConfigured {
+                reply_deposit,
+                reply_timeout,
+            }
+        })
+    }
+
+    pub fn subscribe(&mut self, from: u64, subscriber: u64) -> GOCRunResult<u64> {
+        let reply = block_on(self.as_sender(from).subscribe(subscriber));
+
+        self.result(reply, |subscriber| Event::Subscribed(subscriber.into()))
+    }
+
+    pub fn unsubscribe(&mut self, from: u64, subscriber: u64) -> GOCRunResult<u64> {
+        let reply = block_on(self.as_sender(from).unsubscribe(subscriber));
+
+        self.result(reply, |subscriber| Event::Unsubscribed(subscriber.into()))
+    }
+
+    pub fn gas_reservation(&mut self, from: u64) -> GOCRunResult<(u64, Option<u32>)> {
+        let reply = block_on(self.as_sender(from).gas_reservation());
+
+        self.result(reply, |(reserved_gas, expiration)| Event::GasReservation {
+            reserved_gas,
+            expiration,
+        })
+    }
+
+    #[cfg(feature = "testing")]
+    pub fn override_randomness(
+        &mut self,
+        from: u64,
+        randomness_override: Option<RandomnessOverride>,
+    ) -> RunResult<Option<RandomnessOverride>, TestEvent, Error> {
+        // `Client` only sends actions, so test hooks are sent directly.
         RunResult::new(
-            self.0.send_with_value(from, Action::Enter, value),
-            |actor_id| Event::PlayerAdded(actor_id.into()),
+            self.inner_program()
+                .send(from, TestAction::OverrideRandomness(randomness_override)),
+            TestEvent::RandomnessOverridden,
         )
     }
+}
+
+/// Parameters of [`Action::Start`] that most tests leave at their defaults: a
+/// uniform selection of 1 winner, no raffle & fixed pricing.
+#[derive(Clone, Copy, Debug)]
+pub struct StartOptions {
+    pub selection: Selection,
+    pub raffle: Option<Raffle>,
+    pub pricing: Pricing,
+}
+
+impl Default for StartOptions {
+    fn default() -> Self {
+        Self {
+            selection: Selection::Uniform,
+            raffle: None,
+            pricing: Pricing::Fixed,
+        }
+    }
+}
 
-    pub fn pick_winner(&mut self, from: u64) -> GOCRunResult<ActorId> {
-        RunResult::new(self.0.send(from, Action::PickWinner), Event::Winner)
+impl StartOptions {
+    fn started(
+        self,
+        round: u64,
+        ending: u64,
+        participation_cost: u128,
+        fungible_token: Option<ActorId>,
+    ) -> Event {
+        Event::Started {
+            round,
+            ending,
+            participation_cost,
+            fungible_token,
+            selection: self.selection,
+            raffle: self.raffle,
+            pricing: self.pricing,
+        }
     }
 }
 
-pub struct GOCMetaState<'a>(&'a InnerProgram<'a>);
+pub struct GOCMetaState(State);
 
-impl GOCMetaState<'_> {
+impl GOCMetaState {
     pub fn all(self) -> MetaStateReply<State> {
-        MetaStateReply(self.0.read_state().unwrap())
+        MetaStateReply(self.0)
     }
 }
 
 pub fn predict_winner(system: &System, players: &[u64]) -> ActorId {
-    let mut random_data = [0; 4];
-
-    Xoshiro128PlusPlus::seed_from_u64(system.block_timestamp()).fill_bytes(&mut random_data);
-
-    let mystical_number = u32::from_le_bytes(random_data) as usize;
+    players[winner_index(system.block_timestamp(), players.len())].into()
+}
 
-    players[mystical_number % players.len()].into()
+pub fn predict_winners(
+    system: &System,
+    selection: Selection,
+    players: &[u64],
+    stakes: &[u128],
+) -> Vec<ActorId> {
+    selection
+        .pick(system.block_timestamp(), stakes)
+        .into_iter()
+        .map(|index| players[index].into())
+        .collect()
 }
@@ -1,4 +1,7 @@
-pub use super::{common::Program, Goc, FOREIGN_USER};
+pub use super::{common::Program, Goc, StartOptions, FOREIGN_USER};
 pub use ft_main_io::FTokenEvent;
+#[cfg(feature = "testing")]
+pub use game_of_chance_io::testing::*;
 pub use game_of_chance_io::*;
 pub use gstd::{prelude::*, ActorId};
+pub use gtest::EXISTENTIAL_DEPOSIT;